@@ -0,0 +1,78 @@
+//! Benchmarks of `Sim::roll_until_goal` on typical banners and goals. Run them
+//! with `cargo make bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use feh_sim_seed::{parallel, Banner, Color, Goal, GoalPreset, Sim};
+
+fn scenarios() -> Vec<(&'static str, Banner, Goal)> {
+    let legendary = Banner {
+        focus_sizes: [3, 3, 3, 3],
+        starting_rates: (8, 0),
+        focus_charges: false,
+        fourstar_focus: None,
+    };
+    let fourstar_focus = Banner {
+        fourstar_focus: Some(Color::Red),
+        ..Banner::default()
+    };
+    vec![
+        (
+            "any focus",
+            Banner::default(),
+            Goal::Preset(GoalPreset::AnyFocus, 1),
+        ),
+        (
+            "all focus",
+            Banner::default(),
+            Goal::Preset(GoalPreset::AllFocus, 1),
+        ),
+        (
+            "red focus +10",
+            Banner::default(),
+            Goal::Preset(GoalPreset::RedFocus, 11),
+        ),
+        (
+            "legendary red focus",
+            legendary,
+            Goal::Preset(GoalPreset::RedFocus, 1),
+        ),
+        (
+            "red 4* focus",
+            fourstar_focus,
+            Goal::Preset(GoalPreset::RedFourstarFocus, 1),
+        ),
+    ]
+}
+
+fn roll_until_goal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("roll_until_goal");
+    for (name, banner, goal) in scenarios() {
+        let mut sim = Sim::new(banner, goal);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| sim.roll_until_goal())
+        });
+    }
+    group.finish();
+}
+
+fn parallel_run(c: &mut Criterion) {
+    const TRIALS: u64 = 1 << 18;
+    let goal = Goal::Preset(GoalPreset::AllFocus, 1);
+    let mut group = c.benchmark_group("parallel::run");
+    group
+        .sample_size(10)
+        .throughput(Throughput::Elements(TRIALS));
+    for threads in (0..)
+        .map(|power| 1 << power)
+        .take_while(|&threads| threads <= parallel::available_threads())
+    {
+        group.bench_function(BenchmarkId::new("threads", threads), |b| {
+            b.iter(|| parallel::run(Banner::default(), &goal, TRIALS, 0, threads))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, roll_until_goal, parallel_run);
+criterion_main!(benches);
@@ -1,6 +1,8 @@
 use seed::prelude::*;
 
-use crate::{Color, Msg};
+use std::fmt;
+
+use crate::{i18n::tr, i18n::tr_args, stepper::stepper, Color, Msg, Pool};
 
 /// Representation of a summoning focus.
 #[derive(Copy, Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
@@ -22,7 +24,58 @@ impl Default for Banner {
     }
 }
 
+impl fmt::Display for Banner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [red, blue, green, colorless] = self.focus_sizes;
+        f.write_str(&tr_args(
+            "{five}%/{four}% banner, focus R{red} B{blue} G{green} C{colorless}",
+            &[
+                ("five", &self.starting_rates.0),
+                ("four", &self.starting_rates.1),
+                ("red", &red.max(0)),
+                ("blue", &blue.max(0)),
+                ("green", &green.max(0)),
+                ("colorless", &colorless.max(0)),
+            ],
+        ))?;
+        if let Some(color) = self.fourstar_focus {
+            f.write_str(&tr_args(", {color} 4* focus", &[("color", &color)]))?;
+        }
+        if self.focus_charges {
+            f.write_str(tr(", focus charges"))?;
+        }
+        Ok(())
+    }
+}
+
 impl Banner {
+    /// Number of units of each color in each pool, indexed by `Pool` and then
+    /// by `Color`.
+    pub fn pool_sizes(&self) -> [[u8; 4]; 5] {
+        let mut pool_sizes = [
+            [0, 0, 0, 0],
+            [26, 19, 14, 17],
+            [0, 0, 0, 0],
+            [45, 46, 37, 50],
+            [45, 46, 37, 50],
+        ];
+        for (size, &focus_size) in pool_sizes[Pool::Focus as usize]
+            .iter_mut()
+            .zip(&self.focus_sizes)
+        {
+            *size = focus_size.max(0) as u8;
+        }
+        if let Some(color) = self.fourstar_focus {
+            pool_sizes[Pool::FourstarFocus as usize][color as usize] = 1;
+        }
+        pool_sizes
+    }
+
+    /// Whether units from the general 5* pool can appear on the banner.
+    pub fn has_general_fivestars(&self) -> bool {
+        self.fourstar_focus.is_some() || self.starting_rates.1 > 0
+    }
+
     /// Parses data from the representation used in query strings to share settings.
     pub fn from_query_string(s: &str) -> Option<Self> {
         let data = base64::decode(s).ok()?;
@@ -30,22 +83,85 @@ impl Banner {
     }
 }
 
+/// The starting rates that banners can have, with their descriptions in
+/// English. Descriptions are translated with `tr` before being shown.
+pub const STARTING_RATES: [((u8, u8), &str); 5] = [
+    ((3, 3), "3%/3% (Normal)"),
+    ((5, 3), "5%/3% (Hero Fest)"),
+    ((8, 0), "8%/0% (Legendary)"),
+    ((4, 2), "4%/2% (Weekly Focus)"),
+    ((6, 0), "6%/0% (Double Special Heroes)"),
+];
+
+/// Label and input for the number of focus units of one color.
+fn focus_count(
+    banner: &Banner,
+    color: Color,
+    id: &'static str,
+    label: &'static str,
+) -> Vec<Node<Msg>> {
+    let size = banner.focus_sizes[color as usize];
+    let input = input![
+        id![id],
+        input_ev("input", move |text| {
+            if let Ok(quantity) = text.parse::<i8>() {
+                Msg::BannerFocusSizeChange { color, quantity }
+            } else {
+                Msg::BannerFocusSizeChange {
+                    color,
+                    quantity: -1,
+                }
+            }
+        }),
+        attrs![
+            At::Type => "number";
+            At::Class => "small_number";
+            At::Value => if size >= 0 {
+                size.to_string()
+            } else {
+                "".to_string()
+            };
+            At::Min => 0;
+            At::Required => true;
+        ],
+    ];
+    vec![
+        label![
+            attrs![
+                At::For => id;
+            ],
+            tr(label),
+        ],
+        stepper(
+            input,
+            size as i32,
+            0..=i8::MAX as i32,
+            move |quantity| Msg::BannerFocusSizeChange {
+                color,
+                quantity: quantity as i8,
+            },
+            &tr_args("{color} focus units", &[("color", &color)]),
+        ),
+    ]
+}
+
 /// Section for choosing banner parameters.
 pub fn banner_selector(banner: &Banner) -> Node<Msg> {
-    let rate_option = |rates: (u8, u8), label: &str| -> Node<Msg> {
+    let rate_option = |rates: (u8, u8), label: &'static str| -> Node<Msg> {
         let mut attrs = attrs![
             At::Value => format!("{} {}", rates.0, rates.1);
         ];
         if rates == banner.starting_rates {
             attrs.add(At::Selected, "");
         }
-        option![attrs, label]
+        option![attrs, tr(label)]
     };
     div![
         id!["banner_selector"],
         div![
             select![
                 id!["starting_rates"],
+                attrs!["aria-label" => tr("Starting rates")],
                 input_ev("input", |text| {
                     if let &[Ok(first), Ok(second)] = &*text
                         .split_whitespace()
@@ -59,160 +175,39 @@ pub fn banner_selector(banner: &Banner) -> Node<Msg> {
                         Msg::Null
                     }
                 }),
-                rate_option((3, 3), "3%/3% (Normal)"),
-                rate_option((5, 3), "5%/3% (Hero Fest)"),
-                rate_option((8, 0), "8%/0% (Legendary)"),
-                rate_option((4, 2), "4%/2% (Weekly Focus)"),
-                rate_option((6, 0), "6%/0% (Double Special Heroes)"),
+                STARTING_RATES
+                    .iter()
+                    .map(|&(rates, label)| rate_option(rates, label))
+                    .collect::<Vec<_>>(),
             ],
             input![
                 id!["focus_charges_banner"],
                 simple_ev(Ev::Input, Msg::BannerFocusChargesToggle),
                 attrs![At::Type => "checkbox"; At::Checked => banner.focus_charges.as_at_value()],
             ],
-            label![attrs![At::For => "focus_charges_banner"], "Focus charges?"]
+            label![
+                attrs![At::For => "focus_charges_banner"],
+                tr("Focus charges?")
+            ]
         ],
         div![
             id!["focus_counts"],
-            label![
-                attrs![
-                    At::For => "focus_count_r";
-                ],
-                "R:",
-            ],
-            input![
-                id!["focus_count_r"],
-                input_ev("input", |text| {
-                    if let Ok(quantity) = text.parse::<i8>() {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Red,
-                            quantity,
-                        }
-                    } else {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Red,
-                            quantity: -1,
-                        }
-                    }
-                }),
-                attrs![
-                    At::Type => "number";
-                    At::Class => "small_number";
-                    At::Value => if banner.focus_sizes[0] >= 0 {
-                        banner.focus_sizes[0].to_string()
-                    } else {
-                        "".to_string()
-                    };
-                    At::Min => 0;
-                    At::Required => true;
-                ]
-            ],
-            label![
-                attrs![
-                    At::For => "focus_count_b";
-                ],
-                "B:",
-            ],
-            input![
-                id!["focus_count_b"],
-                input_ev("input", |text| {
-                    if let Ok(quantity) = text.parse::<i8>() {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Blue,
-                            quantity,
-                        }
-                    } else {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Blue,
-                            quantity: -1,
-                        }
-                    }
-                }),
-                attrs![
-                    At::Type => "number";
-                    At::Class => "small_number";
-                    At::Value => if banner.focus_sizes[1] >= 0 {
-                        banner.focus_sizes[1].to_string()
-                    } else {
-                        "".to_string()
-                    };
-                    At::Min => 0;
-                    At::Required => true;
-                ]
-            ],
-            label![
-                attrs![
-                    At::For => "focus_count_g";
-                ],
-                "G:",
-            ],
-            input![
-                id!["focus_count_g"],
-                input_ev("input", |text| {
-                    if let Ok(quantity) = text.parse::<i8>() {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Green,
-                            quantity,
-                        }
-                    } else {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Green,
-                            quantity: -1,
-                        }
-                    }
-                }),
-                attrs![
-                    At::Type => "number";
-                    At::Class => "small_number";
-                    At::Value => if banner.focus_sizes[2] >= 0 {
-                        banner.focus_sizes[2].to_string()
-                    } else {
-                        "".to_string()
-                    };
-                    At::Min => 0;
-                    At::Required => true;
-                ]
-            ],
-            label![
-                attrs![
-                    At::For => "focus_count_c";
-                ],
-                "C:",
-            ],
-            input![
-                id!["focus_count_c"],
-                input_ev("input", |text| {
-                    if let Ok(quantity) = text.parse::<i8>() {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Colorless,
-                            quantity,
-                        }
-                    } else {
-                        Msg::BannerFocusSizeChange {
-                            color: Color::Colorless,
-                            quantity: -1,
-                        }
-                    }
-                }),
-                attrs![
-                    At::Type => "number";
-                    At::Class => "small_number";
-                    At::Value => if banner.focus_sizes[3] >= 0 {
-                        banner.focus_sizes[3].to_string()
-                    } else {
-                        "".to_string()
-                    };
-                    At::Min => 0;
-                    At::Required => true;
-                ],
-            ],
+            [
+                (Color::Red, "focus_count_r", "R:"),
+                (Color::Blue, "focus_count_b", "B:"),
+                (Color::Green, "focus_count_g", "G:"),
+                (Color::Colorless, "focus_count_c", "C:"),
+            ]
+            .iter()
+            .flat_map(|&(color, id, label)| focus_count(banner, color, id, label))
+            .collect::<Vec<_>>(),
             if banner.starting_rates == (3, 3) {
                 nodes![
                     label![
                         attrs![
                             At::For => "fourstar_focus";
                         ],
-                        "4* focus:",
+                        tr("4* focus:"),
                     ],
                     select![
                         id!["fourstar_focus"],
@@ -229,44 +224,49 @@ pub fn banner_selector(banner: &Banner) -> Node<Msg> {
                             Msg::BannerFourstarFocusChange { focus }
                         }),
                         option![
+                            attrs![At::Value => "None"],
                             if banner.fourstar_focus == None {
                                 attrs![At::Selected => "1"]
                             } else {
                                 attrs![]
                             },
-                            "None"
+                            tr("None")
                         ],
                         option![
+                            attrs![At::Value => "Red"],
                             if banner.fourstar_focus == Some(Color::Red) {
                                 attrs![At::Selected => "1"]
                             } else {
                                 attrs![]
                             },
-                            "Red"
+                            Color::Red.to_string()
                         ],
                         option![
+                            attrs![At::Value => "Blue"],
                             if banner.fourstar_focus == Some(Color::Blue) {
                                 attrs![At::Selected => "1"]
                             } else {
                                 attrs![]
                             },
-                            "Blue"
+                            Color::Blue.to_string()
                         ],
                         option![
+                            attrs![At::Value => "Green"],
                             if banner.fourstar_focus == Some(Color::Green) {
                                 attrs![At::Selected => "1"]
                             } else {
                                 attrs![]
                             },
-                            "Green"
+                            Color::Green.to_string()
                         ],
                         option![
+                            attrs![At::Value => "Colorless"],
                             if banner.fourstar_focus == Some(Color::Colorless) {
                                 attrs![At::Selected => "1"]
                             } else {
                                 attrs![]
                             },
-                            "Colorless"
+                            Color::Colorless.to_string()
                         ],
                     ],
                 ]
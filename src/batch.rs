@@ -0,0 +1,122 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{banner::Banner, goal::Goal, histogram::Histogram, sim::Sim, stats};
+
+/// Longest time to spend simulating before letting the page update, in
+/// milliseconds.
+const STEP_MS: f64 = 100.0;
+
+/// Time that pressing Run simulates for, in milliseconds.
+pub const RUN_MS: f64 = 250.0;
+
+/// Number of simulations in the first chunk. Time per simulation varies wildly
+/// depending on device performance and sim parameters, so chunks start small
+/// and double in size from there.
+const FIRST_CHUNK_SIZE: u64 = 100;
+
+/// Most simulations to run between checks of the time.
+const MAX_CHUNK_SIZE: u64 = 500;
+
+/// Runs simulations a chunk at a time until `ms` milliseconds have passed.
+/// `run_chunk` is given the most simulations to run in one chunk, and returns
+/// whether there are more to run after it. Returns whether there are more to
+/// run after this call.
+pub fn run_for(ms: f64, mut run_chunk: impl FnMut(u64) -> bool) -> bool {
+    let perf = seed::window().performance().unwrap();
+    let start = perf.now();
+    let mut chunk_size = FIRST_CHUNK_SIZE;
+    loop {
+        if !run_chunk(chunk_size) {
+            return false;
+        }
+        if perf.now() - start >= ms {
+            return true;
+        }
+        chunk_size = (chunk_size * 2).min(MAX_CHUNK_SIZE);
+    }
+}
+
+/// Runs simulations for a short time, so that the page can keep updating in
+/// between calls. See `run_for` for the arguments.
+pub fn run_for_a_step(run_chunk: impl FnMut(u64) -> bool) -> bool {
+    run_for(STEP_MS, run_chunk)
+}
+
+/// Simulations of a list of banners and goals, each run the same number of
+/// times. The work is done a little at a time so that the page can keep
+/// updating in between.
+#[derive(Default, Debug)]
+pub struct BatchRun {
+    jobs: Vec<(Banner, Goal)>,
+    trials: u32,
+    /// Seed shared by every job, so that they all see the same random numbers
+    /// and differences between their results are mostly due to the jobs
+    /// themselves.
+    seed: u64,
+    /// Results for each job that is done, in order.
+    pub results: Vec<Histogram>,
+    /// The simulator and results so far for the first job that isn't done.
+    current: Option<(Sim, Histogram)>,
+    running: bool,
+}
+
+impl BatchRun {
+    pub fn new(jobs: Vec<(Banner, Goal)>, trials: u32) -> Self {
+        BatchRun {
+            jobs,
+            trials,
+            seed: SmallRng::from_entropy().gen(),
+            results: vec![],
+            current: None,
+            running: true,
+        }
+    }
+
+    /// Number of jobs, including ones that aren't done yet.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// Runs simulations for a short time, returning whether there are more
+    /// to run.
+    pub fn step(&mut self) -> bool {
+        if self.running {
+            self.running = run_for_a_step(|chunk_size| self.run_chunk(chunk_size));
+        }
+        self.running
+    }
+
+    /// Runs up to `chunk_size` simulations of the first job that isn't done,
+    /// returning whether there are more to run.
+    fn run_chunk(&mut self, chunk_size: u64) -> bool {
+        let (banner, goal) = match self.jobs.get(self.results.len()) {
+            Some(job) => job,
+            None => return false,
+        };
+        let seed = self.seed;
+        let (sim, data) = self.current.get_or_insert_with(|| {
+            (
+                Sim::with_seed(*banner, goal.clone(), seed),
+                Histogram::default(),
+            )
+        });
+        let done = stats::count(data);
+        for _ in 0..chunk_size.min(self.trials as u64 - done) {
+            data.record(sim.roll_until_goal());
+        }
+        if stats::count(data) >= self.trials as u64 {
+            let (_, data) = self.current.take().unwrap();
+            self.results.push(data);
+        }
+        self.results.len() < self.jobs.len()
+    }
+}
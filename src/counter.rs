@@ -1,8 +1,10 @@
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 
+use serde::{Deserialize, Serialize};
+
 /// Associative array of u32 -> u32 with the interface and implementation optimized
 /// for use as a counter for small numbers with a dense distribution.
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Counter {
     data: Vec<u32>,
 }
@@ -40,3 +42,50 @@ impl DerefMut for Counter {
         &mut self.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    #[test]
+    fn missing_values_are_zero() {
+        let counter = Counter::default();
+        assert_eq!(counter[0], 0);
+        assert_eq!(counter[1000], 0);
+        assert!(counter.is_empty());
+    }
+
+    #[test]
+    fn incrementing_resizes() {
+        let mut counter = Counter::default();
+        counter[10] += 1;
+        assert_eq!(counter.len(), 11);
+        assert_eq!(counter[10], 1);
+        assert_eq!(counter[9], 0);
+    }
+
+    fn count(values: &[u32]) -> Counter {
+        let mut counter = Counter::default();
+        for &value in values {
+            counter[value] += 1;
+        }
+        counter
+    }
+
+    proptest! {
+        #[test]
+        fn counts_every_increment(values in prop::collection::vec(0u32..500, 0..200)) {
+            let mut counter = Counter::default();
+            for &value in &values {
+                counter[value] += 1;
+            }
+            prop_assert_eq!(counter.iter().sum::<u32>() as usize, values.len());
+            for &value in &values {
+                let expected = values.iter().filter(|&&x| x == value).count();
+                prop_assert_eq!(counter[value] as usize, expected);
+            }
+        }
+    }
+}
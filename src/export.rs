@@ -0,0 +1,178 @@
+use seed::prelude::*;
+
+use wasm_bindgen::JsCast;
+
+use web_sys::{
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, Document, Element, HtmlAnchorElement,
+    HtmlCanvasElement, HtmlImageElement, Url, XmlSerializer,
+};
+
+use crate::theme::Theme;
+
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+
+/// Stylesheet that gets embedded in exported images so they look the same
+/// outside of the page.
+const STYLE: &str = include_str!("../style.css");
+
+/// Size of the exported image in SVG units, matching the graph's viewBox plus
+/// room for the title.
+const VIEW_WIDTH: f64 = 113.0;
+const GRAPH_HEIGHT: f64 = 70.0;
+const TITLE_HEIGHT: f64 = 7.0;
+
+/// Width of exported PNG images in pixels.
+const PNG_WIDTH: f64 = 1600.0;
+
+/// How long to keep a downloaded blob URL alive, in milliseconds. Browsers
+/// start the download after the click returns, and some cancel it if the URL
+/// is already gone.
+const REVOKE_DELAY_MS: i32 = 40_000;
+
+/// Builds a self-contained SVG document out of the graph currently on the page,
+/// with `title` written above it, in the colors of `theme`.
+fn standalone_svg(document: &Document, title: &str, theme: Theme) -> Result<String, JsValue> {
+    let graph: Element = document
+        .get_element_by_id("graph")
+        .ok_or("The graph is not on the page")?
+        .clone_node_with_deep(true)?
+        .dyn_into()?;
+    graph.set_attribute("y", &TITLE_HEIGHT.to_string())?;
+    graph.set_attribute("width", &VIEW_WIDTH.to_string())?;
+    graph.set_attribute("height", &GRAPH_HEIGHT.to_string())?;
+
+    let root = document.create_element_ns(Some(SVG_NS), "svg")?;
+    root.set_attribute("xmlns", SVG_NS)?;
+    // The browser's preference can't be relied on once the image has left the
+    // page, so the theme that it was shown in is written down instead.
+    root.set_attribute("data-theme", theme.resolve().code())?;
+    root.set_attribute(
+        "viewBox",
+        &format!("0 0 {} {}", VIEW_WIDTH, TITLE_HEIGHT + GRAPH_HEIGHT),
+    )?;
+
+    let title_el = document.create_element_ns(Some(SVG_NS), "title")?;
+    title_el.set_text_content(Some(title));
+    root.append_child(&title_el)?;
+
+    let style = document.create_element_ns(Some(SVG_NS), "style")?;
+    style.set_text_content(Some(&format!(
+        "svg {{ font-family: sans-serif; }}\n{}",
+        STYLE
+    )));
+    root.append_child(&style)?;
+
+    let background = document.create_element_ns(Some(SVG_NS), "rect")?;
+    background.set_attribute("width", "100%")?;
+    background.set_attribute("height", "100%")?;
+    background.set_attribute("id", "export_background")?;
+    root.append_child(&background)?;
+
+    let heading = document.create_element_ns(Some(SVG_NS), "text")?;
+    heading.set_attribute("x", &(VIEW_WIDTH / 2.0).to_string())?;
+    heading.set_attribute("y", &(TITLE_HEIGHT / 2.0).to_string())?;
+    heading.set_attribute("text-anchor", "middle")?;
+    heading.set_attribute("dominant-baseline", "middle")?;
+    heading.set_attribute("font-size", "2.5")?;
+    heading.set_attribute("id", "export_title")?;
+    heading.set_text_content(Some(title));
+    root.append_child(&heading)?;
+
+    root.append_child(&graph)?;
+
+    XmlSerializer::new()?.serialize_to_string(&root)
+}
+
+/// Creates a blob URL for the given file contents. The caller is responsible
+/// for revoking it.
+fn blob_url(contents: &str, mime_type: &str) -> Result<String, JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let blob =
+        Blob::new_with_str_sequence_and_options(&parts, BlobPropertyBag::new().type_(mime_type))?;
+    Url::create_object_url_with_blob(&blob)
+}
+
+/// Makes the browser download the contents of `url` as `filename`. Firefox
+/// only follows links that are in the page, so the link is added for the
+/// click and removed again afterwards.
+fn download(document: &Document, url: &str, filename: &str) -> Result<(), JsValue> {
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(url);
+    anchor.set_download(filename);
+    let body = document.body().ok_or("The page has no body")?;
+    body.append_child(&anchor)?;
+    anchor.click();
+    body.remove_child(&anchor)?;
+    Ok(())
+}
+
+/// Revokes a blob URL once the download using it has had time to start.
+fn revoke_later(url: String) -> Result<(), JsValue> {
+    let revoke = Closure::once_into_js(move || {
+        let _ = Url::revoke_object_url(&url);
+    });
+    seed::window().set_timeout_with_callback_and_timeout_and_arguments_0(
+        revoke.unchecked_ref(),
+        REVOKE_DELAY_MS,
+    )?;
+    Ok(())
+}
+
+/// Downloads a text file with the given contents.
+pub fn download_text(contents: &str, mime_type: &str, filename: &str) -> Result<(), JsValue> {
+    let url = blob_url(contents, mime_type)?;
+    let result = download(&seed::document(), &url, filename);
+    revoke_later(url)?;
+    result
+}
+
+/// Downloads the graph as an SVG file with `title` written above it, in the
+/// colors of `theme`.
+pub fn download_svg(title: &str, theme: Theme) -> Result<(), JsValue> {
+    let svg = standalone_svg(&seed::document(), title, theme)?;
+    download_text(&svg, "image/svg+xml", "fehstatsim.svg")
+}
+
+/// Downloads the graph as a PNG file with `title` written above it, in the
+/// colors of `theme`. The image is rasterized by the browser, so the download
+/// starts once it has loaded.
+pub fn download_png(title: &str, theme: Theme) -> Result<(), JsValue> {
+    let document = seed::document();
+    let url = blob_url(&standalone_svg(&document, title, theme)?, "image/svg+xml")?;
+
+    let image = HtmlImageElement::new()?;
+    let onload = {
+        let image = image.clone();
+        let url = url.clone();
+        move || {
+            let result = (|| -> Result<(), JsValue> {
+                let document = seed::document();
+                let canvas: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+                let height = PNG_WIDTH * (TITLE_HEIGHT + GRAPH_HEIGHT) / VIEW_WIDTH;
+                canvas.set_width(PNG_WIDTH as u32);
+                canvas.set_height(height as u32);
+                let context: CanvasRenderingContext2d = canvas
+                    .get_context("2d")?
+                    .ok_or("Canvas rendering is not supported")?
+                    .dyn_into()?;
+                context.draw_image_with_html_image_element_and_dw_and_dh(
+                    &image, 0.0, 0.0, PNG_WIDTH, height,
+                )?;
+                download(
+                    &document,
+                    &canvas.to_data_url_with_type("image/png")?,
+                    "fehstatsim.png",
+                )
+            })();
+            let _ = Url::revoke_object_url(&url);
+            if result.is_err() {
+                crate::alert(crate::i18n::tr("Could not create the image."));
+            }
+        }
+    };
+    image.set_onload(Some(
+        Closure::once_into_js(onload).unchecked_ref::<js_sys::Function>(),
+    ));
+    image.set_src(&url);
+    Ok(())
+}
@@ -8,7 +8,13 @@ use strum_macros::EnumIter;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{banner::Banner, Color, Msg};
+use crate::i18n::{tr, tr_args};
+use crate::stepper::stepper;
+use crate::validation::{self, Issue};
+use crate::{banner::Banner, Color, Msg, Pool};
+
+mod expr;
+pub use expr::{nested_goal_selector, GoalExpr};
 
 /// Pre-set options for common goals.
 #[derive(Copy, Clone, Debug, EnumIter, PartialEq, Eq, Serialize, Deserialize)]
@@ -29,10 +35,12 @@ pub enum GoalPreset {
     ColorlessFourstarFocus,
 }
 
-impl fmt::Display for GoalPreset {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl GoalPreset {
+    /// The English name of the preset, which stays the same whatever the
+    /// language of the interface.
+    pub fn name(self) -> &'static str {
         use crate::goal::GoalPreset::*;
-        let s = match *self {
+        match self {
             AnyFocus => "Any 5* focus unit",
             AllFocus => "All focus units",
             RedFocus => "Specific red 5* focus unit",
@@ -47,8 +55,13 @@ impl fmt::Display for GoalPreset {
             ColorlessFocus => "Specific colorless 5* focus unit",
             AnyColorless => "Any colorless 5* focus unit",
             ColorlessFourstarFocus => "The colorless 4* focus unit",
-        };
-        f.write_str(s)
+        }
+    }
+}
+
+impl fmt::Display for GoalPreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(tr(self.name()))
     }
 }
 
@@ -109,33 +122,179 @@ impl GoalPreset {
     }
 }
 
-/// Whether the given goal is to achieve all of the goal parts or just a single one.
+/// How many of the goal parts need to be achieved to complete the goal.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GoalKind {
     Any,
     All,
+    /// At least the given number of goal parts.
+    AtLeast(u8),
+    /// The given number of copies in total, counting every copy of any of the
+    /// goal units. The number of copies of each individual part is ignored.
+    Total(u8),
+}
+
+impl GoalKind {
+    /// The name of the kind, ignoring its threshold, as used by the goal editor.
+    fn name(self) -> &'static str {
+        match self {
+            GoalKind::Any => "Any",
+            GoalKind::All => "All",
+            GoalKind::AtLeast(_) => "AtLeast",
+            GoalKind::Total(_) => "Total",
+        }
+    }
+
+    /// How many children a group of this kind with `children` children needs
+    /// to be able to complete for the group to be completable.
+    pub fn children_needed(self, children: usize) -> usize {
+        match self {
+            GoalKind::Any | GoalKind::Total(_) => 1,
+            GoalKind::All => children.max(1),
+            GoalKind::AtLeast(count) => (count as usize).max(1),
+        }
+    }
+
+    /// The threshold of a threshold goal kind.
+    fn threshold(self) -> Option<u8> {
+        match self {
+            GoalKind::AtLeast(count) | GoalKind::Total(count) => Some(count),
+            GoalKind::Any | GoalKind::All => None,
+        }
+    }
 }
 
 /// A single unit that the goal is trying to obtain.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GoalPart {
     pub unit_color: Color,
     pub num_copies: u8,
-    pub four_star: bool,
+    /// The pool that the unit is chosen from. This used to be a flag for the 4*
+    /// focus unit, so it is stored in a way that old goals still load as
+    /// either 5* or 4* focus units.
+    #[serde(alias = "four_star", with = "part_pool")]
+    pub pool: Pool,
+}
+
+/// Serialization for `GoalPart::pool` as a single number, where 0 and 1 match
+/// the `false` and `true` of the old 4* focus flag.
+mod part_pool {
+    use std::fmt;
+
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    use crate::Pool;
+
+    const POOLS: [Pool; 5] = [
+        Pool::Focus,
+        Pool::FourstarFocus,
+        Pool::Fivestar,
+        Pool::Fourstar,
+        Pool::Threestar,
+    ];
+
+    pub fn serialize<S: Serializer>(pool: &Pool, serializer: S) -> Result<S::Ok, S::Error> {
+        let index = POOLS.iter().position(|p| p == pool).unwrap();
+        serializer.serialize_u8(index as u8)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pool, D::Error> {
+        struct PoolVisitor;
+
+        impl<'de> Visitor<'de> for PoolVisitor {
+            type Value = Pool;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a pool number or a 4* focus flag")
+            }
+
+            fn visit_bool<E: de::Error>(self, four_star: bool) -> Result<Pool, E> {
+                Ok(POOLS[four_star as usize])
+            }
+
+            fn visit_u64<E: de::Error>(self, index: u64) -> Result<Pool, E> {
+                POOLS
+                    .get(index as usize)
+                    .copied()
+                    .ok_or_else(|| E::custom(format!("invalid pool {}", index)))
+            }
+        }
+
+        // Formats like bincode can't tell what type is next, but they store
+        // the old flag as the same single byte as a number.
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PoolVisitor)
+        } else {
+            deserializer.deserialize_u8(PoolVisitor)
+        }
+    }
 }
 
 /// The flexible representation of a goal
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CustomGoal {
     pub kind: GoalKind,
     pub goals: Vec<GoalPart>,
 }
 
 /// The goal of a summoning session.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Goal {
     Custom(CustomGoal),
     Preset(GoalPreset, u8),
+    Expr(GoalExpr),
+}
+
+impl fmt::Display for GoalPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x {}", self.num_copies, self.unit_color)?;
+        if self.pool != Pool::Focus {
+            write!(f, " {}", self.pool)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Goal::Preset(preset, count) => {
+                write!(f, "{}", preset)?;
+                if preset.is_single_target() && *count > 1 {
+                    write!(f, " x{}", count)?;
+                }
+                Ok(())
+            }
+            Goal::Custom(custom_goal) => {
+                match custom_goal.kind {
+                    GoalKind::Any => f.write_str(tr("Any of: "))?,
+                    GoalKind::All => f.write_str(tr("All of: "))?,
+                    GoalKind::AtLeast(count) => {
+                        f.write_str(&tr_args("At least {count} of: ", &[("count", &count)]))?
+                    }
+                    GoalKind::Total(count) => {
+                        f.write_str(&tr_args("{count} copies total of: ", &[("count", &count)]))?
+                    }
+                }
+                for (i, part) in custom_goal.goals.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if let GoalKind::Total(_) = custom_goal.kind {
+                        write!(f, "{}", part.unit_color)?;
+                        if part.pool != Pool::Focus {
+                            write!(f, " {}", part.pool)?;
+                        }
+                    } else {
+                        write!(f, "{}", part)?;
+                    }
+                }
+                Ok(())
+            }
+            Goal::Expr(expr) => f.write_str(&tr_args("Nested: {goal}", &[("goal", expr)])),
+        }
+    }
 }
 
 impl Default for Goal {
@@ -155,6 +314,7 @@ impl Goal {
         let (preset, count) = match self {
             Goal::Preset(preset, count) => (*preset, *count),
             Goal::Custom(custom) => return custom.clone(),
+            Goal::Expr(expr) => return expr.to_custom(),
         };
 
         let count = if preset.is_single_target() {
@@ -173,11 +333,11 @@ impl Goal {
             goals: vec![],
         };
 
-        let mut add_color_goal = |color: Color, four_star: bool| {
+        let mut add_color_goal = |color: Color, pool: Pool| {
             custom_goal.goals.push(GoalPart {
                 unit_color: color,
                 num_copies: count,
-                four_star,
+                pool,
             });
         };
         // Add an individual GoalPart for each focus unit that matches the
@@ -186,51 +346,48 @@ impl Goal {
             AllFocus | AnyFocus => {
                 for idx in 0..banner.focus_sizes.len() {
                     for _ in 0..banner.focus_sizes[idx] {
-                        add_color_goal(Color::try_from(idx as u8).unwrap(), false);
+                        add_color_goal(Color::try_from(idx as u8).unwrap(), Pool::Focus);
                     }
                 }
             }
-            RedFocus => add_color_goal(Red, false),
-            BlueFocus => add_color_goal(Blue, false),
-            GreenFocus => add_color_goal(Green, false),
-            ColorlessFocus => add_color_goal(Colorless, false),
+            RedFocus => add_color_goal(Red, Pool::Focus),
+            BlueFocus => add_color_goal(Blue, Pool::Focus),
+            GreenFocus => add_color_goal(Green, Pool::Focus),
+            ColorlessFocus => add_color_goal(Colorless, Pool::Focus),
             AnyRed => {
                 for _ in 0..banner.focus_sizes[0] {
-                    add_color_goal(Red, false)
+                    add_color_goal(Red, Pool::Focus)
                 }
             }
             AnyBlue => {
                 for _ in 0..banner.focus_sizes[1] {
-                    add_color_goal(Blue, false)
+                    add_color_goal(Blue, Pool::Focus)
                 }
             }
             AnyGreen => {
                 for _ in 0..banner.focus_sizes[2] {
-                    add_color_goal(Green, false)
+                    add_color_goal(Green, Pool::Focus)
                 }
             }
             AnyColorless => {
                 for _ in 0..banner.focus_sizes[3] {
-                    add_color_goal(Colorless, false)
+                    add_color_goal(Colorless, Pool::Focus)
                 }
             }
-            RedFourstarFocus => add_color_goal(Red, true),
-            BlueFourstarFocus => add_color_goal(Blue, true),
-            GreenFourstarFocus => add_color_goal(Green, true),
-            ColorlessFourstarFocus => add_color_goal(Colorless, true),
+            RedFourstarFocus => add_color_goal(Red, Pool::FourstarFocus),
+            BlueFourstarFocus => add_color_goal(Blue, Pool::FourstarFocus),
+            GreenFourstarFocus => add_color_goal(Green, Pool::FourstarFocus),
+            ColorlessFourstarFocus => add_color_goal(Colorless, Pool::FourstarFocus),
         }
 
         custom_goal
     }
 
-    /// Checks whether or not the goal is possible on the given banner.
-    pub fn is_available(&self, banner: &Banner) -> bool {
+    /// Convert the goal into a nested goal expression.
+    pub fn as_expr(&self, banner: &Banner) -> GoalExpr {
         match self {
-            Goal::Custom(custom_goal) => custom_goal
-                .goals
-                .iter()
-                .any(|&GoalPart { unit_color, .. }| banner.focus_sizes[unit_color as usize] > 0),
-            Goal::Preset(preset, _) => preset.is_available(banner),
+            Goal::Expr(expr) => expr.clone(),
+            _ => GoalExpr::from_custom(&self.as_custom(banner)),
         }
     }
 
@@ -245,6 +402,7 @@ impl Goal {
 pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
     let mut select = select![
         id!["goal"],
+        attrs!["aria-label" => tr("Goal")],
         input_ev("input", |text| {
             if let Some(preset) = text
                 .parse::<u8>()
@@ -254,6 +412,8 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
                 Msg::GoalPresetChange { preset }
             } else if text == "custom" {
                 Msg::GoalMakeCustom
+            } else if text == "nested" {
+                Msg::GoalMakeNested
             } else {
                 Msg::Null
             }
@@ -270,24 +430,48 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
         } else {
             attrs![]
         },
-        "Custom goal",
+        tr("Custom goal"),
+    ]);
+    select.add_child(option![
+        attrs![
+            At::Value => "nested";
+        ],
+        if let Goal::Expr(_) = goal {
+            attrs![
+                At::Selected => "";
+            ]
+        } else {
+            attrs![]
+        },
+        tr("Nested goal"),
     ]);
     for preset in GoalPreset::iter() {
         let mut attrs = attrs! [
             At::Value => preset as usize;
         ];
-        if !preset.is_available(banner) {
-            attrs.add(At::Disabled, "");
-        } else if let Goal::Preset(goal_preset, _) = goal {
+        if let Goal::Preset(goal_preset, _) = goal {
             if *goal_preset == preset {
                 attrs.add(At::Selected, "");
             }
         }
-        select.add_child(option![attrs, preset.to_string(),]);
+        let text = if preset.is_available(banner) {
+            preset.to_string()
+        } else {
+            tr_args("{goal} (not on this banner)", &[("goal", &preset)])
+        };
+        select.add_child(option![attrs, text]);
     }
+    let issues = validation::validate(goal, banner);
     div![
         id!["goal_selector"],
         select,
+        if let Goal::Expr(_) = goal {
+            // Nested goals show the problems with their outermost group inside
+            // the editor.
+            seed::empty()
+        } else {
+            validation::issue_messages(&issues, &[])
+        },
         if let Goal::Preset(preset, count) = goal {
             if preset.is_single_target() {
                 span![
@@ -295,29 +479,37 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
                         attrs![
                             At::For => "goal_count";
                         ],
-                        "Count: ",
+                        tr("Count: "),
                     ],
-                    input![
-                        id!["goal_count"],
-                        input_ev("input", |text| {
-                            if let Ok(quantity) = text.parse::<u8>() {
-                                Msg::GoalPresetQuantityChange { quantity }
-                            } else {
-                                Msg::GoalPresetQuantityChange { quantity: 0 }
-                            }
-                        }),
-                        attrs! [
-                            At::Type => "number";
-                            At::Value => if *count > 0 {
-                                count.to_string()
-                            } else {
-                                "".to_string()
-                            };
-                            At::Class => "small_number";
-                            At::Min => 1;
-                            At::Required => true;
+                    stepper(
+                        input![
+                            id!["goal_count"],
+                            input_ev("input", |text| {
+                                if let Ok(quantity) = text.parse::<u8>() {
+                                    Msg::GoalPresetQuantityChange { quantity }
+                                } else {
+                                    Msg::GoalPresetQuantityChange { quantity: 0 }
+                                }
+                            }),
+                            attrs! [
+                                At::Type => "number";
+                                At::Value => if *count > 0 {
+                                    count.to_string()
+                                } else {
+                                    "".to_string()
+                                };
+                                At::Class => "small_number";
+                                At::Min => 1;
+                                At::Required => true;
+                            ],
                         ],
-                    ]
+                        *count as i32,
+                        1..=u8::MAX as i32,
+                        |quantity| Msg::GoalPresetQuantityChange {
+                            quantity: quantity as u8
+                        },
+                        tr("Copies"),
+                    ),
                 ]
             } else {
                 seed::empty()
@@ -325,97 +517,79 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
         } else {
             seed::empty()
         },
-        advanced_goal_selector(goal),
+        advanced_goal_selector(goal, &issues),
+        if let Goal::Expr(expr) = goal {
+            nested_goal_selector(expr, &issues)
+        } else {
+            seed::empty()
+        },
     ]
 }
 
 /// Subsection for selecting the goal using the detailed representation instead of
 /// a preset.
-fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
+fn advanced_goal_selector(goal: &Goal, issues: &[Issue]) -> Node<Msg> {
     if let Goal::Custom(custom_goal) = goal {
         let mut base = div![style!["margin-left" => "2em";]];
-        if custom_goal.goals.len() > 1 {
-            base.add_child(select![
-                input_ev(Ev::Input, |text| match &*text {
-                    "Any" => Msg::GoalKindChange {
-                        kind: GoalKind::Any
-                    },
-                    "All" => Msg::GoalKindChange {
-                        kind: GoalKind::All
-                    },
-                    _ => Msg::Null,
-                }),
-                option![
-                    attrs![
-                        At::Value => "Any";
-                    ],
-                    if custom_goal.kind == GoalKind::Any {
-                        attrs![At::Selected => ""]
-                    } else {
-                        attrs![]
-                    },
-                    "Any of these",
-                ],
-                option![
-                    attrs![
-                        At::Value => "All";
-                    ],
-                    if custom_goal.kind == GoalKind::All {
-                        attrs![At::Selected => ""]
-                    } else {
-                        attrs![]
-                    },
-                    "All of these",
-                ],
-            ]);
+        if custom_goal.goals.len() > 1 || custom_goal.kind.threshold().is_some() {
+            base.add_child(goal_kind_selector(custom_goal.kind, |kind| {
+                Msg::GoalKindChange { kind }
+            }));
         }
 
         for (index, goal_part) in custom_goal.goals.iter().enumerate() {
-            let mut color_select = select![input_ev(Ev::Input, move |value| {
-                if let Some(color) = value
-                    .parse::<u8>()
-                    .ok()
-                    .and_then(|num| Color::try_from(num).ok())
-                {
-                    Msg::GoalPartColorChange { index, color }
-                } else {
-                    Msg::Null
-                }
-            }),];
-            for color in Color::iter() {
-                let mut attrs = attrs![At::Value => color as usize];
-                if goal_part.unit_color == color {
-                    attrs.add(At::Selected, "");
-                }
-                color_select.add_child(option![attrs, color.to_string()]);
-            }
             base.add_child(div![
                 button![
                     simple_ev(
                         Ev::Click,
                         Msg::GoalPartQuantityChange { index, quantity: 0 }
                     ),
+                    attrs!["aria-label" => tr("Remove this unit")],
                     "X",
                 ],
-                input![
-                    input_ev(Ev::Input, move |value| {
-                        if let Ok(quantity) = value.parse::<u8>() {
-                            Msg::GoalPartQuantityChange { index, quantity }
-                        } else {
-                            Msg::Null
-                        }
-                    }),
-                    attrs![
-                        At::Type => "number";
-                        At::Class => "small_number";
-                        At::Min => 0;
-                        At::Required => true;
-                        At::Value => goal_part.num_copies;
+                if let GoalKind::Total(_) = custom_goal.kind {
+                    span![tr(" A specific ")]
+                } else {
+                    span![
+                        // Stepping down stops at one copy, since zero copies
+                        // removes the unit.
+                        stepper(
+                            input![
+                                input_ev(Ev::Input, move |value| {
+                                    if let Ok(quantity) = value.parse::<u8>() {
+                                        Msg::GoalPartQuantityChange { index, quantity }
+                                    } else {
+                                        Msg::Null
+                                    }
+                                }),
+                                attrs![
+                                    At::Type => "number";
+                                    At::Class => "small_number";
+                                    At::Min => 0;
+                                    At::Required => true;
+                                    At::Value => goal_part.num_copies;
+                                    "aria-label" => tr("Copies");
+                                ]
+                            ],
+                            goal_part.num_copies as i32,
+                            1..=u8::MAX as i32,
+                            move |quantity| Msg::GoalPartQuantityChange {
+                                index,
+                                quantity: quantity as u8,
+                            },
+                            tr("Copies"),
+                        ),
+                        tr(" copies of a specific "),
                     ]
-                ],
-                " copies of a specific ",
-                color_select,
-                " unit",
+                },
+                color_select(goal_part.unit_color, move |color| {
+                    Msg::GoalPartColorChange { index, color }
+                }),
+                tr(" unit"),
+                pool_select(*goal_part, move |pool| {
+                    Msg::GoalPartPoolChange { index, pool }
+                }),
+                validation::issue_messages(issues, &[index]),
             ]);
         }
 
@@ -427,6 +601,7 @@ fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
                     quantity: 1
                 }
             ),
+            attrs!["aria-label" => tr("Add a unit")],
             "+",
         ]);
 
@@ -435,3 +610,126 @@ fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
         seed::empty()
     }
 }
+
+/// Selector for the pool that a goal part's unit is from.
+fn pool_select(part: GoalPart, on_change: impl Fn(Pool) -> Msg + Clone + 'static) -> Node<Msg> {
+    let mut select = select![
+        class!["padleft"],
+        attrs!["aria-label" => tr("Pool")],
+        input_ev(Ev::Input, move |value| {
+            if let Some(pool) = value
+                .parse::<u8>()
+                .ok()
+                .and_then(|num| Pool::try_from(num).ok())
+            {
+                on_change(pool)
+            } else {
+                Msg::Null
+            }
+        })
+    ];
+    for &pool in &[
+        Pool::Focus,
+        Pool::FourstarFocus,
+        Pool::Fivestar,
+        Pool::Fourstar,
+        Pool::Threestar,
+    ] {
+        let mut attrs = attrs![At::Value => pool as usize];
+        if part.pool == pool {
+            attrs.add(At::Selected, "");
+        }
+        select.add_child(option![attrs, pool.to_string()]);
+    }
+    select
+}
+
+/// Selector for the color of a goal part.
+fn color_select(selected: Color, on_change: impl Fn(Color) -> Msg + Clone + 'static) -> Node<Msg> {
+    let mut select = select![
+        attrs!["aria-label" => tr("Color")],
+        input_ev(Ev::Input, move |value| {
+            if let Some(color) = value
+                .parse::<u8>()
+                .ok()
+                .and_then(|num| Color::try_from(num).ok())
+            {
+                on_change(color)
+            } else {
+                Msg::Null
+            }
+        })
+    ];
+    for color in Color::iter() {
+        let mut attrs = attrs![At::Value => color as usize];
+        if selected == color {
+            attrs.add(At::Selected, "");
+        }
+        select.add_child(option![attrs, color.to_string()]);
+    }
+    select
+}
+
+/// Selector for how many of the parts of a goal need to be achieved, along
+/// with the threshold for the kinds that have one.
+fn goal_kind_selector(
+    kind: GoalKind,
+    on_change: impl Fn(GoalKind) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    let threshold_change = on_change.clone();
+    let mut select = select![
+        attrs!["aria-label" => tr("How many of these are needed")],
+        input_ev(Ev::Input, move |text| match &*text {
+            "Any" => on_change(GoalKind::Any),
+            "All" => on_change(GoalKind::All),
+            "AtLeast" => on_change(GoalKind::AtLeast(kind.threshold().unwrap_or(2))),
+            "Total" => on_change(GoalKind::Total(kind.threshold().unwrap_or(2))),
+            _ => Msg::Null,
+        })
+    ];
+    for &(option_kind, text) in &[
+        (GoalKind::Any, "Any of these"),
+        (GoalKind::All, "All of these"),
+        (GoalKind::AtLeast(0), "At least this many of these:"),
+        (GoalKind::Total(0), "This many copies in total of these:"),
+    ] {
+        select.add_child(option![
+            attrs![
+                At::Value => option_kind.name();
+            ],
+            if kind.name() == option_kind.name() {
+                attrs![At::Selected => ""]
+            } else {
+                attrs![]
+            },
+            tr(text),
+        ]);
+    }
+    div![
+        select,
+        if let Some(threshold) = kind.threshold() {
+            input![
+                class!["padleft"],
+                input_ev(Ev::Input, move |value| {
+                    match value.parse::<u8>() {
+                        Ok(count) if count > 0 => threshold_change(match kind {
+                            GoalKind::Total(_) => GoalKind::Total(count),
+                            _ => GoalKind::AtLeast(count),
+                        }),
+                        _ => Msg::Null,
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Min => 1;
+                    At::Required => true;
+                    At::Value => threshold;
+                    "aria-label" => tr("Number needed");
+                ]
+            ]
+        } else {
+            seed::empty()
+        },
+    ]
+}
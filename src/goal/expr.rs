@@ -0,0 +1,353 @@
+use seed::prelude::*;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{color_select, goal_kind_selector, pool_select, CustomGoal, GoalKind, GoalPart};
+use crate::i18n::{tr, tr_args};
+use crate::stepper::stepper;
+use crate::validation::{self, Issue};
+use crate::{banner::Banner, Color, Msg, Pool};
+
+/// A goal made of nested groups of goal parts, for targets like "a red unit and
+/// a blue unit, or 5 copies of a colorless unit".
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoalExpr {
+    /// A single unit. Units with the same pool, color, and index are the same
+    /// unit, even when they are in different parts of the goal. The 4* focus
+    /// unit is always the first 5* focus unit of its color, and counts copies
+    /// pulled from either pool.
+    Part { part: GoalPart, unit: u8 },
+    /// A group of goals that is complete when enough of its children are, using
+    /// the same kinds as a flat custom goal.
+    Group {
+        kind: GoalKind,
+        children: Vec<GoalExpr>,
+    },
+}
+
+impl Default for GoalExpr {
+    fn default() -> Self {
+        GoalExpr::Part {
+            part: GoalPart {
+                unit_color: Color::Red,
+                num_copies: 1,
+                pool: Pool::Focus,
+            },
+            unit: 0,
+        }
+    }
+}
+
+impl fmt::Display for GoalExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoalExpr::Part { part, unit } => {
+                write!(f, "{}x {}", part.num_copies, part.unit_color)?;
+                if part.pool != Pool::Focus {
+                    write!(f, " {}", part.pool)?;
+                }
+                if part.pool != Pool::FourstarFocus {
+                    write!(f, " #{}", unit + 1)?;
+                }
+                Ok(())
+            }
+            GoalExpr::Group { kind, children } => {
+                let separator = match kind {
+                    GoalKind::Any => tr(" or "),
+                    GoalKind::All => tr(" and "),
+                    GoalKind::AtLeast(count) => {
+                        f.write_str(&tr_args("at least {count} of ", &[("count", count)]))?;
+                        ", "
+                    }
+                    GoalKind::Total(count) => {
+                        f.write_str(&tr_args("{count} copies total of ", &[("count", count)]))?;
+                        ", "
+                    }
+                };
+                write!(f, "(")?;
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    write!(f, "{}", child)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl GoalExpr {
+    /// A group containing a single new part.
+    pub fn new_group() -> Self {
+        GoalExpr::Group {
+            kind: GoalKind::All,
+            children: vec![GoalExpr::default()],
+        }
+    }
+
+    /// Converts a flat custom goal into a single group. Parts of the same pool
+    /// and color are given consecutive unit indexes, the same as in the flat
+    /// goal. The 4* focus unit is the first 5* focus unit of its color, so the
+    /// other 5* focus parts of that color start after it.
+    pub fn from_custom(custom: &CustomGoal) -> Self {
+        let mut next_unit = [[0; 4]; 5];
+        for part in &custom.goals {
+            if part.pool == Pool::FourstarFocus {
+                next_unit[Pool::Focus as usize][part.unit_color as usize] = 1;
+            }
+        }
+        let children = custom
+            .goals
+            .iter()
+            .map(|&part| {
+                let unit = if part.pool == Pool::FourstarFocus {
+                    0
+                } else {
+                    let unit = &mut next_unit[part.pool as usize][part.unit_color as usize];
+                    *unit += 1;
+                    *unit - 1
+                };
+                GoalExpr::Part { part, unit }
+            })
+            .collect();
+        GoalExpr::Group {
+            kind: custom.kind,
+            children,
+        }
+    }
+
+    /// Converts into a flat custom goal, keeping every part and the kind of the
+    /// outermost group. Nesting and unit indexes are lost.
+    pub fn to_custom(&self) -> CustomGoal {
+        let mut goals = vec![];
+        self.for_each_part(&mut |part, _| goals.push(part));
+        let kind = match self {
+            GoalExpr::Group { kind, .. } => *kind,
+            GoalExpr::Part { .. } => GoalKind::All,
+        };
+        CustomGoal { kind, goals }
+    }
+
+    /// Calls `f` with every part in the expression and its unit index.
+    pub fn for_each_part(&self, f: &mut impl FnMut(GoalPart, u8)) {
+        match self {
+            GoalExpr::Part { part, unit } => f(*part, *unit),
+            GoalExpr::Group { children, .. } => {
+                for child in children {
+                    child.for_each_part(f);
+                }
+            }
+        }
+    }
+
+    /// Checks whether it is possible to complete this part of the goal. A
+    /// group can be completed if enough of its children can be for its kind.
+    /// `in_total` says whether the expression is directly inside a
+    /// `GoalKind::Total` group, which ignores the copy counts of its parts.
+    pub fn can_complete(&self, banner: &Banner, in_total: bool) -> bool {
+        match self {
+            GoalExpr::Part { part, unit } => {
+                let color = part.unit_color as usize;
+                let pool_size = banner.pool_sizes()[part.pool as usize][color];
+                (in_total || part.num_copies > 0)
+                    && match part.pool {
+                        Pool::FourstarFocus => {
+                            pool_size > 0 && banner.pool_sizes()[Pool::Focus as usize][color] > 0
+                        }
+                        Pool::Fivestar => banner.has_general_fivestars() && *unit < pool_size,
+                        _ => *unit < pool_size,
+                    }
+            }
+            GoalExpr::Group { kind, children } => {
+                let in_total = matches!(kind, GoalKind::Total(_));
+                let possible = children
+                    .iter()
+                    .filter(|child| child.can_complete(banner, in_total))
+                    .count();
+                possible >= kind.children_needed(children.len())
+            }
+        }
+    }
+
+    /// Finds the node at the given path of child indexes.
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut GoalExpr> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&index, rest)) => match self {
+                GoalExpr::Group { children, .. } => children.get_mut(index)?.node_mut(rest),
+                GoalExpr::Part { .. } => None,
+            },
+        }
+    }
+
+    /// Removes the node at the given path. The outermost group can't be removed.
+    pub fn remove_node(&mut self, path: &[usize]) {
+        if let Some((&index, parent)) = path.split_last() {
+            if let Some(GoalExpr::Group { children, .. }) = self.node_mut(parent) {
+                if index < children.len() {
+                    children.remove(index);
+                }
+            }
+        }
+    }
+}
+
+/// Editor for a nested goal, with each group indented below its kind selector.
+pub fn nested_goal_selector(expr: &GoalExpr, issues: &[Issue]) -> Node<Msg> {
+    div![
+        id!["nested_goal"],
+        style!["margin-left" => "2em";],
+        node_editor(expr, issues, vec![], false),
+    ]
+}
+
+/// Editor for the node of a nested goal at the given path.
+fn node_editor(expr: &GoalExpr, issues: &[Issue], path: Vec<usize>, in_total: bool) -> Node<Msg> {
+    let remove_button = if path.is_empty() {
+        seed::empty()
+    } else {
+        button![
+            simple_ev(Ev::Click, Msg::GoalNodeRemove { path: path.clone() }),
+            attrs!["aria-label" => tr("Remove")],
+            "X",
+        ]
+    };
+    match *expr {
+        GoalExpr::Part { part, unit } => {
+            let color_path = path.clone();
+            let copies_path = path.clone();
+            let step_path = path.clone();
+            let unit_path = path.clone();
+            let messages = validation::issue_messages(issues, &path);
+            div![
+                class!["goal_part"],
+                remove_button,
+                if in_total {
+                    span![tr(" A specific ")]
+                } else {
+                    span![
+                        stepper(
+                            input![
+                                input_ev(Ev::Input, move |value| {
+                                    if let Ok(num_copies) = value.parse::<u8>() {
+                                        Msg::GoalNodePartChange {
+                                            path: copies_path,
+                                            part: GoalPart { num_copies, ..part },
+                                            unit,
+                                        }
+                                    } else {
+                                        Msg::Null
+                                    }
+                                }),
+                                attrs![
+                                    At::Type => "number";
+                                    At::Class => "small_number";
+                                    At::Min => 0;
+                                    At::Required => true;
+                                    At::Value => part.num_copies;
+                                    "aria-label" => tr("Copies");
+                                ]
+                            ],
+                            part.num_copies as i32,
+                            1..=u8::MAX as i32,
+                            move |num_copies| Msg::GoalNodePartChange {
+                                path: step_path.clone(),
+                                part: GoalPart {
+                                    num_copies: num_copies as u8,
+                                    ..part
+                                },
+                                unit,
+                            },
+                            tr("Copies"),
+                        ),
+                        tr(" copies of a specific "),
+                    ]
+                },
+                color_select(part.unit_color, move |unit_color| {
+                    Msg::GoalNodePartChange {
+                        path: color_path.clone(),
+                        part: GoalPart { unit_color, ..part },
+                        unit,
+                    }
+                }),
+                if part.pool == Pool::FourstarFocus {
+                    span![tr(" unit")]
+                } else {
+                    span![
+                        tr(" unit, number "),
+                        input![
+                            input_ev(Ev::Input, move |value| {
+                                match value.parse::<u8>() {
+                                    Ok(number) if number > 0 => Msg::GoalNodePartChange {
+                                        path: unit_path,
+                                        part,
+                                        unit: number - 1,
+                                    },
+                                    _ => Msg::Null,
+                                }
+                            }),
+                            attrs![
+                                At::Type => "number";
+                                At::Class => "small_number";
+                                At::Min => 1;
+                                At::Required => true;
+                                At::Value => unit + 1;
+                                "aria-label" => tr("Unit number");
+                            ]
+                        ],
+                    ]
+                },
+                pool_select(part, move |pool| Msg::GoalNodePartChange {
+                    path: path.clone(),
+                    part: GoalPart { pool, ..part },
+                    unit,
+                }),
+                messages,
+            ]
+        }
+        GoalExpr::Group { kind, ref children } => {
+            let kind_path = path.clone();
+            let mut child_list = div![style!["margin-left" => "2em";]];
+            let child_in_total = matches!(kind, GoalKind::Total(_));
+            for (index, child) in children.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                child_list.add_child(node_editor(child, issues, child_path, child_in_total));
+            }
+            div![
+                class!["goal_group"],
+                div![
+                    style!["display" => "flex";],
+                    remove_button,
+                    goal_kind_selector(kind, move |kind| Msg::GoalNodeKindChange {
+                        path: kind_path.clone(),
+                        kind,
+                    }),
+                    validation::issue_messages(issues, &path),
+                ],
+                child_list,
+                div![
+                    style!["margin-left" => "2em";],
+                    button![
+                        simple_ev(
+                            Ev::Click,
+                            Msg::GoalNodeAdd {
+                                path: path.clone(),
+                                group: false,
+                            }
+                        ),
+                        tr("+ unit"),
+                    ],
+                    button![
+                        class!["padleft"],
+                        simple_ev(Ev::Click, Msg::GoalNodeAdd { path, group: true }),
+                        tr("+ group"),
+                    ],
+                ],
+            ]
+        }
+    }
+}
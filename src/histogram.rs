@@ -0,0 +1,410 @@
+use std::cell::OnceCell;
+use std::ops::{Add, AddAssign};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::counter::Counter;
+
+/// Number of significant bits kept for each value by default. Every value
+/// below 2^12 = 4096 orbs is counted exactly, and larger ones are grouped into
+/// buckets less than 0.05% of their value wide.
+const DEFAULT_SIGNIFICANT_BITS: u32 = 12;
+
+/// Counts of how many times each number of orbs came up, with 64-bit counts
+/// so that long native runs can't overflow them.
+///
+/// Values can optionally be grouped into buckets that get wider as the values
+/// get larger, keeping the significant bits of each value and dropping the
+/// rest, like a floating-point number does. That keeps the memory used by
+/// goals with very long tails bounded, no matter how large the values get. A
+/// value in a bucket is treated as the bucket's smallest value.
+///
+/// Running totals of the counts are cached the first time they are needed
+/// after a change, so that percentiles and other cumulative queries don't
+/// need to add up every count each time.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    /// Number of significant bits kept for each value, or `None` to give
+    /// every value its own bucket.
+    significant_bits: Option<u32>,
+    /// Running totals of `counts`, cleared whenever a count changes.
+    cumulative: OnceCell<Vec<u64>>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram::with_significant_bits(DEFAULT_SIGNIFICANT_BITS)
+    }
+}
+
+impl PartialEq for Histogram {
+    fn eq(&self, other: &Histogram) -> bool {
+        self.significant_bits == other.significant_bits && self.counts == other.counts
+    }
+}
+
+impl Histogram {
+    /// A histogram that counts every value exactly. Its memory use grows with
+    /// the largest value seen.
+    pub fn exact() -> Self {
+        Histogram {
+            counts: vec![],
+            total: 0,
+            significant_bits: None,
+            cumulative: OnceCell::new(),
+        }
+    }
+
+    /// A histogram that counts values below `2^bits` exactly, and groups
+    /// larger values into buckets that keep `bits` significant bits.
+    pub fn with_significant_bits(bits: u32) -> Self {
+        assert!(
+            (1..=32).contains(&bits),
+            "significant bits must be in 1..=32"
+        );
+        Histogram {
+            significant_bits: Some(bits),
+            ..Histogram::exact()
+        }
+    }
+
+    /// Index of the bucket that holds `value`.
+    fn bucket(&self, value: u32) -> usize {
+        match self.significant_bits {
+            Some(bits) if value.checked_shr(bits).unwrap_or(0) > 0 => {
+                let highest_bit = 31 - value.leading_zeros();
+                let shift = highest_bit + 1 - bits;
+                ((shift as usize) << (bits - 1)) + (value >> shift) as usize
+            }
+            _ => value as usize,
+        }
+    }
+
+    /// The smallest value in a bucket.
+    fn bucket_start(&self, bucket: usize) -> u32 {
+        match self.significant_bits {
+            Some(bits) if bucket.checked_shr(bits).unwrap_or(0) > 0 => {
+                let shift = (bucket >> (bits - 1)) - 1;
+                ((bucket - (shift << (bits - 1))) << shift) as u32
+            }
+            _ => bucket as u32,
+        }
+    }
+
+    /// Counts one occurrence of `value`.
+    pub fn record(&mut self, value: u32) {
+        self.add_count(value, 1);
+    }
+
+    /// Counts `count` occurrences of `value`.
+    pub fn add_count(&mut self, value: u32, count: u64) {
+        let bucket = self.bucket(value);
+        if bucket >= self.counts.len() {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += count;
+        self.total += count;
+        self.cumulative.take();
+    }
+
+    /// Removes all counts.
+    pub fn clear(&mut self) {
+        self.counts.clear();
+        self.total = 0;
+        self.cumulative.take();
+    }
+
+    /// Total number of values counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Number of values counted in the same bucket as `value`.
+    pub fn count(&self, value: u32) -> u64 {
+        self.counts.get(self.bucket(value)).copied().unwrap_or(0)
+    }
+
+    /// The smallest value of each bucket that has been counted, and its count,
+    /// in increasing order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u32, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(move |(bucket, &count)| (self.bucket_start(bucket), count))
+    }
+
+    /// The largest value counted, or `None` if there is no data.
+    pub fn max_value(&self) -> Option<u32> {
+        self.iter().next_back().map(|(value, _)| value)
+    }
+
+    /// Running totals of the counts of each bucket.
+    fn cumulative(&self) -> &[u64] {
+        self.cumulative.get_or_init(|| {
+            self.counts
+                .iter()
+                .scan(0, |total, &count| {
+                    *total += count;
+                    Some(*total)
+                })
+                .collect()
+        })
+    }
+
+    /// Number of values counted that are at most `value`, including the rest
+    /// of its bucket.
+    pub fn count_at_most(&self, value: u32) -> u64 {
+        let cumulative = self.cumulative();
+        match cumulative.get(self.bucket(value)) {
+            Some(&count) => count,
+            None => self.total,
+        }
+    }
+
+    /// Number of values counted in the range `lo..hi`, going by the buckets
+    /// that the ends of the range fall into.
+    pub fn count_between(&self, lo: u32, hi: u32) -> u64 {
+        if hi <= lo {
+            return 0;
+        }
+        let below = if lo == 0 {
+            0
+        } else {
+            self.count_at_most(lo - 1)
+        };
+        self.count_at_most(hi - 1) - below
+    }
+
+    /// The smallest value whose running total satisfies `done`, or the largest
+    /// value counted if none does. `done` must be false for every running
+    /// total below some point, and true for every one from there on. Returns 0
+    /// if there is no data.
+    pub fn first_value_where(&self, done: impl Fn(u64) -> bool) -> u32 {
+        let cumulative = self.cumulative();
+        match cumulative.partition_point(|&total| !done(total)) {
+            bucket if bucket < cumulative.len() => self.bucket_start(bucket),
+            _ => self.max_value().unwrap_or(0),
+        }
+    }
+}
+
+impl AddAssign<&Histogram> for Histogram {
+    /// Adds the counts from another histogram. If the two group values into
+    /// different buckets, the other's buckets are counted at their smallest
+    /// values.
+    fn add_assign(&mut self, other: &Histogram) {
+        if self.significant_bits != other.significant_bits {
+            for (value, count) in other.iter() {
+                self.add_count(value, count);
+            }
+            return;
+        }
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, &other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+        self.total += other.total;
+        self.cumulative.take();
+    }
+}
+
+impl AddAssign for Histogram {
+    fn add_assign(&mut self, other: Histogram) {
+        *self += &other;
+    }
+}
+
+impl Add for Histogram {
+    type Output = Histogram;
+
+    fn add(mut self, other: Histogram) -> Self::Output {
+        self += &other;
+        self
+    }
+}
+
+/// Saved as the number of significant bits followed by the count of each
+/// bucket. The running totals are worked out again when they are needed.
+impl Serialize for Histogram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.significant_bits, &self.counts).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Histogram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Histogram, D::Error> {
+        let (significant_bits, counts): (Option<u32>, Vec<u64>) =
+            Deserialize::deserialize(deserializer)?;
+        if significant_bits.is_some_and(|bits| !(1..=32).contains(&bits)) {
+            return Err(de::Error::custom("significant bits must be in 1..=32"));
+        }
+        Ok(Histogram {
+            total: counts.iter().sum(),
+            counts,
+            significant_bits,
+            cumulative: OnceCell::new(),
+        })
+    }
+}
+
+impl<'a> Extend<&'a Histogram> for Histogram {
+    /// Adds the counts from each of the other histograms.
+    fn extend<I: IntoIterator<Item = &'a Histogram>>(&mut self, iter: I) {
+        for other in iter {
+            *self += other;
+        }
+    }
+}
+
+impl From<&Counter> for Histogram {
+    fn from(counter: &Counter) -> Self {
+        let mut histogram = Histogram::default();
+        for (value, &count) in counter.iter().enumerate() {
+            if count > 0 {
+                histogram.add_count(value as u32, count as u64);
+            }
+        }
+        histogram
+    }
+}
+
+impl From<&Histogram> for Counter {
+    /// Converts to 32-bit counts of each value. Counts too large for them are
+    /// capped, and values in buckets are counted at their smallest values.
+    fn from(histogram: &Histogram) -> Self {
+        let mut counter = Counter::default();
+        for (value, count) in histogram.iter() {
+            counter[value] = count.min(u32::MAX as u64) as u32;
+        }
+        counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    fn count(values: &[u32], bits: Option<u32>) -> Histogram {
+        let mut histogram = match bits {
+            Some(bits) => Histogram::with_significant_bits(bits),
+            None => Histogram::exact(),
+        };
+        for &value in values {
+            histogram.record(value);
+        }
+        histogram
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        let histogram = count(&[3, 3, 4095], None);
+        assert_eq!(histogram.count(3), 2);
+        assert_eq!(histogram.count(4), 0);
+        let bucketed = count(&[3, 3, 4095], Some(DEFAULT_SIGNIFICANT_BITS));
+        assert!(bucketed.iter().eq(histogram.iter()));
+    }
+
+    #[test]
+    fn large_values_share_buckets() {
+        let histogram = count(&[1000, 1001, 1002, 1003, 1004], Some(4));
+        // 960 to 1023 all have the same top 4 bits.
+        assert_eq!(histogram.count(1000), 5);
+        assert_eq!(histogram.max_value(), Some(960));
+        assert!(histogram.counts.len() < 100);
+    }
+
+    #[test]
+    fn memory_is_bounded() {
+        let histogram = count(&[u32::MAX], Some(8));
+        assert!(histogram.counts.len() <= 26 * 128);
+        assert_eq!(histogram.max_value(), Some(255 << 24));
+    }
+
+    #[test]
+    fn counts_do_not_overflow() {
+        let mut histogram = Histogram::default();
+        histogram.add_count(5, u32::MAX as u64);
+        histogram.add_count(5, u32::MAX as u64);
+        assert_eq!(histogram.total(), 2 * u32::MAX as u64);
+        assert_eq!(Counter::from(&histogram)[5], u32::MAX);
+    }
+
+    #[test]
+    fn serializes_counts_and_buckets() {
+        let histogram = count(&[3, 3, 1000, 5000], Some(8));
+        let data = bincode::serialize(&histogram).unwrap();
+        let loaded: Histogram = bincode::deserialize(&data).unwrap();
+        assert_eq!(loaded, histogram);
+        assert_eq!(loaded.total(), 4);
+
+        let bad_bits = bincode::serialize(&(Some(40u32), vec![1u64])).unwrap();
+        assert!(bincode::deserialize::<Histogram>(&bad_bits).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn buckets_contain_their_values(value in any::<u32>(), bits in 1u32..=32) {
+            let histogram = Histogram::with_significant_bits(bits);
+            let bucket = histogram.bucket(value);
+            let start = histogram.bucket_start(bucket);
+            prop_assert!(start <= value);
+            prop_assert_eq!(histogram.bucket(start), bucket);
+            if bucket > 0 {
+                prop_assert!(histogram.bucket(start - 1) == bucket - 1);
+            }
+        }
+
+        #[test]
+        fn cumulative_counts_match_counting(
+            values in prop::collection::vec(0u32..100_000, 0..200),
+            query in 0u32..100_000,
+            bits in prop::option::of(4u32..16),
+        ) {
+            let mut histogram = count(&values, bits);
+            // Query once so that the cache is filled, and then make sure
+            // that adding more data replaces it.
+            histogram.count_at_most(query);
+            histogram.record(query);
+            let bucket = histogram.bucket(query);
+            let expected = values
+                .iter()
+                .chain(&[query])
+                .filter(|&&value| histogram.bucket(value) <= bucket)
+                .count();
+            prop_assert_eq!(histogram.count_at_most(query), expected as u64);
+            prop_assert_eq!(histogram.total(), values.len() as u64 + 1);
+        }
+
+        #[test]
+        fn adding_counts_both(
+            first in prop::collection::vec(0u32..100_000, 0..200),
+            second in prop::collection::vec(0u32..100_000, 0..200),
+        ) {
+            let both: Vec<u32> = first.iter().chain(&second).copied().collect();
+            let expected = count(&both, Some(8));
+            prop_assert_eq!(count(&first, Some(8)) + count(&second, Some(8)), expected.clone());
+
+            let mut extended = Histogram::with_significant_bits(8);
+            extended.extend(&[count(&first, None), count(&second, Some(8))]);
+            prop_assert_eq!(extended, expected);
+        }
+
+        #[test]
+        fn converts_from_saved_results(values in prop::collection::vec(0u32..5000, 0..200)) {
+            let histogram = count(&values, None);
+            let counter = Counter::from(&histogram);
+            prop_assert_eq!(Histogram::from(&counter), count(&values, Some(DEFAULT_SIGNIFICANT_BITS)));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use seed::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, tr, tr_args};
+use crate::storage::{self, StoreError};
+use crate::{banner::Banner, goal::Goal, histogram::Histogram, precision::Precision, Msg};
+
+/// Local storage key for the banner, goal, and results that were last open.
+/// Saves don't record which fields they have, so the number in the key goes
+/// up whenever `Session` changes, and saves in older formats are left unread
+/// instead of misread.
+const SESSION_KEY: &str = "feh-sim-session-3";
+
+/// Local storage key for the list of past runs, numbered like `SESSION_KEY`.
+const HISTORY_KEY: &str = "feh-sim-history-3";
+
+/// Maximum number of past runs to keep. The oldest ones are dropped first.
+const MAX_HISTORY: usize = 20;
+
+/// A set of results along with the settings that produced them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub banner: Banner,
+    pub goal: Goal,
+    pub data: Histogram,
+    /// How precise the results were when they came from a run to precision.
+    pub precision: Option<Precision>,
+}
+
+/// A past run, saved so that it can be reopened later.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the run was started, in milliseconds since the Unix epoch. Also
+    /// serves to identify the entry.
+    pub timestamp: f64,
+    pub session: Session,
+}
+
+/// Saves the currently open settings and results. `current` identifies the
+/// history entry that the results belong to, if any. If there is no room left
+/// to save them, drops the oldest entries from `history` to make some.
+pub fn save_session(
+    current: Option<f64>,
+    banner: &Banner,
+    goal: &Goal,
+    data: &Histogram,
+    precision: Option<Precision>,
+    history: &mut Vec<HistoryEntry>,
+) -> Result<(), StoreError> {
+    let session = Session {
+        banner: *banner,
+        goal: goal.clone(),
+        data: data.clone(),
+        precision,
+    };
+    loop {
+        match storage::store(SESSION_KEY, &(current, &session)) {
+            Err(StoreError::QuotaExceeded) if !history.is_empty() => {
+                history.pop();
+                save_history(history)?;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Loads the settings and results that were open the last time the app was
+/// used, along with the history entry that they belong to.
+pub fn load_session() -> Option<(Option<f64>, Session)> {
+    storage::load(SESSION_KEY)
+}
+
+/// Saves the list of past runs. If there is no room left to save all of them,
+/// drops the oldest ones until the rest fit.
+pub fn save_history(history: &mut Vec<HistoryEntry>) -> Result<(), StoreError> {
+    loop {
+        match storage::store(HISTORY_KEY, history) {
+            Err(StoreError::QuotaExceeded) if !history.is_empty() => {
+                history.pop();
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Loads the list of past runs, newest first.
+pub fn load_history() -> Vec<HistoryEntry> {
+    storage::load(HISTORY_KEY).unwrap_or_default()
+}
+
+/// Records the results of a run in the history. If `current` identifies an
+/// existing entry, that entry is updated with the new results instead of
+/// adding another. Returns the timestamp identifying the entry.
+pub fn record_run(history: &mut Vec<HistoryEntry>, current: Option<f64>, session: Session) -> f64 {
+    if let Some(entry) = current.and_then(|timestamp| {
+        history
+            .iter_mut()
+            .find(|entry| entry.timestamp == timestamp)
+    }) {
+        entry.session = session;
+        return entry.timestamp;
+    }
+    let timestamp = js_sys::Date::now();
+    history.insert(0, HistoryEntry { timestamp, session });
+    history.truncate(MAX_HISTORY);
+    timestamp
+}
+
+/// Formats a timestamp as a date and time in the user's locale.
+fn format_timestamp(timestamp: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(timestamp))
+        .to_locale_string(i18n::language().code(), &JsValue::UNDEFINED)
+        .into()
+}
+
+/// Section listing past runs, with buttons to reopen or delete each one.
+/// `current` identifies the entry whose results are currently shown, if any.
+pub fn history_list(history: &[HistoryEntry], current: Option<f64>) -> Node<Msg> {
+    if history.is_empty() {
+        return seed::empty();
+    }
+    let mut list = ul![];
+    for (index, entry) in history.iter().enumerate() {
+        let session = &entry.session;
+        list.add_child(li![
+            if Some(entry.timestamp) == current {
+                class!["current"]
+            } else {
+                class![]
+            },
+            button![simple_ev(Ev::Click, Msg::HistoryOpen { index }), tr("Open")],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::HistoryDelete { index }),
+                tr("Delete")
+            ],
+            span![
+                class!["padleft"],
+                tr_args(
+                    "{time}: {goal} - {banner} ({count} samples)",
+                    &[
+                        ("time", &format_timestamp(entry.timestamp)),
+                        ("goal", &session.goal),
+                        ("banner", &session.banner),
+                        ("count", &i18n::number(session.data.total() as f64, 0)),
+                    ],
+                ),
+            ],
+            match &session.precision {
+                Some(precision) => span![
+                    class!["padleft"],
+                    tr_args(
+                        "within ±{error}",
+                        &[("error", &i18n::percent(precision.relative_error / 100.0, 2))],
+                    ),
+                ],
+                None => empty![],
+            },
+        ]);
+    }
+    details![
+        id!["history"],
+        summary![tr_args("History ({count})", &[("count", &history.len())])],
+        list,
+    ]
+}
@@ -0,0 +1,276 @@
+//! Translations of the interface into the languages that it supports.
+//!
+//! Messages are identified by their English text, so English needs no
+//! catalogue and any message missing from another language's catalogue falls
+//! back to English. The current language is kept here rather than in the
+//! model so that `Display` implementations, which can't be passed anything,
+//! can use it too.
+
+use seed::prelude::*;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{query_string, storage, Msg};
+
+mod ja;
+
+/// Local storage key for the chosen language.
+const LANGUAGE_KEY: &str = "feh-sim-language";
+
+/// A language that the interface can be shown in.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Language {
+    /// The language's code, as used in URLs and the page's `lang` attribute.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    /// Finds the language for a code like "ja" or "ja-JP".
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code.split(['-', '_']).next()?;
+        Language::iter().find(|language| language.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// The language's name, written in the language itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    /// Translations from English into the language.
+    fn catalogue(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::Japanese => ja::MESSAGES,
+        }
+    }
+}
+
+thread_local! {
+    static LANGUAGE: Cell<Language> = Cell::new(Language::default());
+    static JAPANESE: HashMap<&'static str, &'static str> =
+        Language::Japanese.catalogue().iter().copied().collect();
+}
+
+/// The language that the interface is currently shown in.
+pub fn language() -> Language {
+    LANGUAGE.with(Cell::get)
+}
+
+/// Changes the language that the interface is shown in.
+pub fn set_language(language: Language) {
+    LANGUAGE.with(|current| current.set(language));
+}
+
+/// Translates a message into the current language.
+pub fn tr(message: &'static str) -> &'static str {
+    match language() {
+        Language::English => Some(message),
+        Language::Japanese => JAPANESE.with(|catalogue| catalogue.get(message).copied()),
+    }
+    .unwrap_or(message)
+}
+
+/// Translates a message into the current language and fills in its
+/// placeholders, which are written as `{name}`.
+pub fn tr_args(message: &'static str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut text = tr(message).to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+/// Formats a number with `decimals` digits after the decimal point, and
+/// commas between groups of thousands. English and Japanese both write
+/// numbers this way.
+pub fn number(value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value.abs());
+    let (whole, fraction) = match text.find('.') {
+        Some(point) => (&text[..point], Some(&text[point + 1..])),
+        None => (&text[..], None),
+    };
+
+    let mut formatted = String::new();
+    if value < 0.0
+        && text
+            .bytes()
+            .any(|digit| digit.is_ascii_digit() && digit != b'0')
+    {
+        formatted.push('-');
+    }
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        formatted.push('.');
+        formatted.push_str(fraction);
+    }
+    formatted
+}
+
+/// Formats a fraction as a percentage with `decimals` digits after the
+/// decimal point.
+pub fn percent(fraction: f64, decimals: usize) -> String {
+    format!("{}%", number(fraction * 100.0, decimals))
+}
+
+/// Shows the page in the given language, marking it as being in that
+/// language for browsers and screen readers.
+pub fn apply_language(language: Language) {
+    set_language(language);
+    if let Some(root) = seed::window()
+        .document()
+        .and_then(|document| document.document_element())
+    {
+        let _ = root.set_attribute("lang", language.code());
+    }
+}
+
+/// Saves the chosen language so that it is used the next time the app is
+/// opened. The app works fine in the browser's language if it can't be saved.
+pub fn save_language(language: Language) {
+    let _ = storage::store(LANGUAGE_KEY, &language);
+}
+
+/// Puts the language in the URL of the current page, so that links copied
+/// from it open in the same language. Replaces the current history entry
+/// instead of adding one, since the page itself doesn't change.
+pub fn show_language_in_url(language: Language) {
+    let window = seed::window();
+    let location = window.location();
+    if let (Ok(path), Ok(search), Ok(history)) =
+        (location.pathname(), location.search(), window.history())
+    {
+        let search = query_string::set(search.trim_start_matches('?'), "lang", language.code());
+        let url = format!("{}?{}", path, search);
+        let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+    }
+}
+
+/// The language that was chosen the last time the app was used, if any.
+pub fn load_language() -> Option<Language> {
+    storage::load(LANGUAGE_KEY)
+}
+
+/// The language that the browser prefers, if it is supported.
+pub fn browser_language() -> Option<Language> {
+    Language::from_code(&seed::window().navigator().language()?)
+}
+
+/// Selector for the language of the interface.
+pub fn language_select() -> Node<Msg> {
+    let current = language();
+    select![
+        id!["language"],
+        attrs!["aria-label" => "Language / 言語"],
+        input_ev(Ev::Input, |code| match Language::from_code(&code) {
+            Some(language) => Msg::LanguageChange { language },
+            None => Msg::Null,
+        }),
+        Language::iter()
+            .map(|language| option![
+                attrs![
+                    At::Value => language.code();
+                    At::Selected => (language == current).as_at_value();
+                ],
+                language.name()
+            ])
+            .collect::<Vec<_>>(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The names of the placeholders in a message.
+    fn placeholders(message: &str) -> Vec<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split('}').next())
+            .collect()
+    }
+
+    #[test]
+    fn catalogues_keep_placeholders() {
+        for language in Language::iter() {
+            for &(english, translated) in language.catalogue() {
+                let mut expected = placeholders(english);
+                let mut found = placeholders(translated);
+                expected.sort_unstable();
+                found.sort_unstable();
+                assert_eq!(expected, found, "{:?}: {}", language, english);
+            }
+        }
+    }
+
+    #[test]
+    fn catalogues_have_no_duplicates() {
+        for language in Language::iter() {
+            let mut messages: Vec<&str> = language
+                .catalogue()
+                .iter()
+                .map(|&(english, _)| english)
+                .collect();
+            messages.sort_unstable();
+            let count = messages.len();
+            messages.dedup();
+            assert_eq!(messages.len(), count, "{:?}", language);
+        }
+    }
+
+    #[test]
+    fn translates_and_falls_back_to_english() {
+        set_language(Language::Japanese);
+        assert_eq!(tr("Run"), "実行");
+        assert_eq!(tr("not a message"), "not a message");
+        assert_eq!(
+            tr_args("{count} samples", &[("count", &number(1234.0, 0))]),
+            "1,234 サンプル"
+        );
+        set_language(Language::English);
+        assert_eq!(tr("Run"), "Run");
+    }
+
+    #[test]
+    fn numbers_are_grouped() {
+        assert_eq!(number(0.0, 0), "0");
+        assert_eq!(number(999.0, 1), "999.0");
+        assert_eq!(number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(number(-1234.0, 0), "-1,234");
+        assert_eq!(number(-0.01, 1), "0.0");
+        assert_eq!(percent(0.125, 1), "12.5%");
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for language in Language::iter() {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::from_code("ja-JP"), Some(Language::Japanese));
+        assert_eq!(Language::from_code("EN_us"), Some(Language::English));
+        assert_eq!(Language::from_code("fr"), None);
+    }
+}
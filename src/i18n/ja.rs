@@ -0,0 +1,352 @@
+//! Japanese translations, keyed by the English text of each message.
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    // Header and navigation
+    ("How to use", "使い方"),
+    ("Scenarios", "シナリオ"),
+    ("Sweep", "一括比較"),
+    ("Sensitivity", "感度分析"),
+    ("Changelog", "更新履歴"),
+    ("Contact", "お問い合わせ"),
+    ("Back", "戻る"),
+    ("Theme", "テーマ"),
+    ("Follow system", "システム設定に従う"),
+    ("Light", "ライト"),
+    ("Dark", "ダーク"),
+    ("High contrast", "ハイコントラスト"),
+    ("Decrease {name}", "{name}を減らす"),
+    ("Increase {name}", "{name}を増やす"),
+    ("{color} focus units", "{color}のピックアップ人数"),
+    // Running and results
+    ("Run", "実行"),
+    ("More", "追加で実行"),
+    ("Stop", "停止"),
+    ("Undo", "元に戻す"),
+    ("Undo (Ctrl+Z)", "元に戻す (Ctrl+Z)"),
+    ("Redo", "やり直す"),
+    ("Redo (Ctrl+Y)", "やり直す (Ctrl+Y)"),
+    (
+        "The goal can't be completed on this banner",
+        "このガチャでは目標を達成できません",
+    ),
+    (
+        "The permalink format has changed, please update your link.",
+        "パーマリンクの形式が変わりました。リンクを更新してください。",
+    ),
+    ("Invalid permalink", "無効なパーマリンクです"),
+    (
+        "Could not create the image.",
+        "画像を作成できませんでした。",
+    ),
+    (
+        "Could not read the file.",
+        "ファイルを読み込めませんでした。",
+    ),
+    (
+        "The file does not contain valid scenarios.",
+        "ファイルに有効なシナリオが含まれていません。",
+    ),
+    (
+        "Could not export the scenarios.",
+        "シナリオを書き出せませんでした。",
+    ),
+    (
+        "The scenarios could not be saved in the browser.",
+        "シナリオをブラウザに保存できませんでした。",
+    ),
+    (
+        "Could not export the sweep results.",
+        "一括比較の結果を書き出せませんでした。",
+    ),
+    // Colors and pools
+    ("Red", "赤"),
+    ("Blue", "青"),
+    ("Green", "緑"),
+    ("Colorless", "無色"),
+    ("5* focus", "★5ピックアップ"),
+    ("general 5*", "★5通常"),
+    ("4* focus", "★4ピックアップ"),
+    ("general 4*", "★4通常"),
+    ("general 3*", "★3通常"),
+    // Banner
+    (
+        "{five}%/{four}% banner, focus R{red} B{blue} G{green} C{colorless}",
+        "{five}%/{four}%ガチャ、ピックアップ 赤{red} 青{blue} 緑{green} 無{colorless}",
+    ),
+    (", {color} 4* focus", "、{color}の★4ピックアップ"),
+    (", focus charges", "、ピックアップチャージあり"),
+    ("Starting rates", "初期排出率"),
+    ("3%/3% (Normal)", "3%/3%（通常）"),
+    ("5%/3% (Hero Fest)", "5%/3%（英雄祭）"),
+    ("8%/0% (Legendary)", "8%/0%（伝承英雄）"),
+    ("4%/2% (Weekly Focus)", "4%/2%（週替わりピックアップ）"),
+    ("6%/0% (Double Special Heroes)", "6%/0%（ダブル超英雄）"),
+    ("Focus charges?", "ピックアップチャージ"),
+    ("R:", "赤:"),
+    ("B:", "青:"),
+    ("G:", "緑:"),
+    ("C:", "無:"),
+    ("4* focus:", "★4ピックアップ:"),
+    ("None", "なし"),
+    // Goals
+    ("Goal", "目標"),
+    ("Custom goal", "カスタム目標"),
+    ("Nested goal", "入れ子の目標"),
+    (
+        "{goal} (not on this banner)",
+        "{goal}（このガチャには不在）",
+    ),
+    ("Any 5* focus unit", "★5ピックアップのいずれか"),
+    ("All focus units", "ピックアップ全員"),
+    ("Specific red 5* focus unit", "特定の赤★5ピックアップ"),
+    ("The red 4* focus unit", "赤の★4ピックアップ"),
+    ("Any red 5* focus unit", "赤★5ピックアップのいずれか"),
+    ("Specific blue 5* focus unit", "特定の青★5ピックアップ"),
+    ("The blue 4* focus unit", "青の★4ピックアップ"),
+    ("Any blue 5* focus unit", "青★5ピックアップのいずれか"),
+    ("Specific green 5* focus unit", "特定の緑★5ピックアップ"),
+    ("The green 4* focus unit", "緑の★4ピックアップ"),
+    ("Any green 5* focus unit", "緑★5ピックアップのいずれか"),
+    (
+        "Specific colorless 5* focus unit",
+        "特定の無色★5ピックアップ",
+    ),
+    (
+        "Any colorless 5* focus unit",
+        "無色★5ピックアップのいずれか",
+    ),
+    ("The colorless 4* focus unit", "無色の★4ピックアップ"),
+    ("Any of: ", "いずれか: "),
+    ("All of: ", "すべて: "),
+    ("At least {count} of: ", "{count}体以上: "),
+    ("{count} copies total of: ", "合計{count}体: "),
+    ("Nested: {goal}", "入れ子: {goal}"),
+    ("Any of these", "これらのいずれか"),
+    ("All of these", "これらのすべて"),
+    ("At least this many of these:", "これらのうち指定数以上:"),
+    (
+        "This many copies in total of these:",
+        "これらの合計でこの体数:",
+    ),
+    ("Count: ", "数: "),
+    ("Remove this unit", "このキャラを削除"),
+    ("Remove", "削除"),
+    ("Copies", "体数"),
+    (" copies of a specific ", " 体、特定の "),
+    (" A specific ", " 特定の "),
+    (" unit", " キャラ"),
+    (" unit, number ", " キャラ、番号 "),
+    ("Unit number", "キャラ番号"),
+    ("Add a unit", "キャラを追加"),
+    ("+ unit", "+ キャラ"),
+    ("+ group", "+ グループ"),
+    ("Pool", "排出枠"),
+    ("Color", "色"),
+    ("How many of these are needed", "必要な数"),
+    ("Number needed", "必要数"),
+    (" or ", " または "),
+    (" and ", " かつ "),
+    ("at least {count} of ", "{count}体以上: "),
+    ("{count} copies total of ", "合計{count}体: "),
+    // Goal problems
+    (
+        "This goal has no units on the banner.",
+        "この目標のキャラはガチャにいません。",
+    ),
+    (
+        "This banner has no {color} focus units.",
+        "このガチャには{color}のピックアップがいません。",
+    ),
+    (
+        "This banner doesn't have {count} {color} {pool} units.",
+        "このガチャには{color}の{pool}が{count}体いません。",
+    ),
+    (
+        "This banner has no {color} 4* focus unit.",
+        "このガチャには{color}の★4ピックアップがいません。",
+    ),
+    (
+        "This banner has no general 5* units.",
+        "このガチャには★5通常キャラがいません。",
+    ),
+    (
+        "No copies are needed of this unit.",
+        "このキャラは1体も必要ありません。",
+    ),
+    (
+        "This is the same unit as another one in this group.",
+        "このグループの他のキャラと同じキャラです。",
+    ),
+    (
+        "This needs {needed} of these to be completed, but only {possible} can be.",
+        "{needed}体の達成が必要ですが、達成できるのは{possible}体だけです。",
+    ),
+    // Graph and summary
+    ("Percentiles", "パーセンタイル"),
+    ("Histogram", "ヒストグラム"),
+    ("Chance of success", "達成確率"),
+    ("Chance of failure", "未達成確率"),
+    ("Percentile", "パーセンタイル"),
+    ("Orbs spent", "消費オーブ"),
+    ("Share of results", "結果の割合"),
+    ("Log scale", "対数目盛"),
+    ("Reset zoom", "ズームを戻す"),
+    ("{count} samples", "{count} サンプル"),
+    ("{pct}%: {orbs} orbs", "{pct}%: {orbs}個"),
+    ("{orbs} orbs: {pct}% done", "{orbs}個: {pct}% 達成"),
+    ("{orbs} orbs: {pct}% not done", "{orbs}個: {pct}% 未達成"),
+    ("{lo}-{hi} orbs: {pct}%", "{lo}～{hi}個: {pct}%"),
+    (
+        "Graph of {y} by {x}. Use the left and right arrow keys to move the \
+         highlighted point, holding Shift for bigger steps.",
+        "{x}ごとの{y}のグラフ。左右の矢印キーで強調する点を動かせます。\
+         Shiftキーを押しながらだと大きく動きます。",
+    ),
+    (
+        "Graph of the results, empty until the simulation is run",
+        "結果のグラフ。シミュレーションを実行するまでは空です。",
+    ),
+    (
+        "Orbs spent before reaching the goal, by percentile of results",
+        "目標達成までの消費オーブ（結果のパーセンタイル別）",
+    ),
+    ("Download SVG", "SVGをダウンロード"),
+    ("Download PNG", "PNGをダウンロード"),
+    ("{orbs} orbs", "{orbs}個"),
+    (" orbs", " 個"),
+    ("Mean", "平均"),
+    ("Median", "中央値"),
+    ("Mode", "最頻値"),
+    ("Std. deviation", "標準偏差"),
+    ("Worst 10% average", "下位10%の平均"),
+    ("Chance of more than ", "消費が次を超える確率: "),
+    // History
+    ("Open", "開く"),
+    ("Delete", "削除"),
+    ("History ({count})", "履歴 ({count})"),
+    (
+        "{time}: {goal} - {banner} ({count} samples)",
+        "{time}: {goal} - {banner}（{count} サンプル）",
+    ),
+    ("within ±{error}", "誤差 ±{error}"),
+    (
+        "The results could not be saved in the browser, so they will be lost \
+         when the page is closed.",
+        "結果をブラウザに保存できなかったため、ページを閉じると失われます。",
+    ),
+    // Run to precision
+    ("Run to precision", "精度指定で実行"),
+    ("Standard error within ±", "標準誤差 ±"),
+    ("% of the value", "% 以内"),
+    ("Percentiles:", "パーセンタイル:"),
+    ("Stop after at most ", "最大 "),
+    (" samples", " サンプルで停止"),
+    ("Run until precise", "精度に達するまで実行"),
+    (
+        "Choose at least one percentile.",
+        "パーセンタイルを1つ以上選んでください。",
+    ),
+    (
+        "{count} samples, within ±{error} (target ±{target}%)",
+        "{count} サンプル、誤差 ±{error}（目標 ±{target}%）",
+    ),
+    (
+        "Running: {progress}, {share} of the sample limit",
+        "実行中: {progress}、上限の{share}",
+    ),
+    ("Reached the target: {progress}", "目標に到達: {progress}"),
+    (
+        "Stopped before reaching the target: {progress}",
+        "目標に届かず停止: {progress}",
+    ),
+    // Scenarios
+    ("Saved scenarios", "保存したシナリオ"),
+    (
+        "Current setup: {goal} - {banner}",
+        "現在の設定: {goal} - {banner}",
+    ),
+    ("Name: ", "名前: "),
+    ("Tags: ", "タグ: "),
+    ("comma, separated", "カンマ区切り"),
+    ("Save current setup", "現在の設定を保存"),
+    ("Import from file: ", "ファイルから読み込む: "),
+    ("Export to file", "ファイルに書き出す"),
+    ("Filter: ", "絞り込み: "),
+    ("name or tag", "名前またはタグ"),
+    (
+        "No scenarios saved yet.",
+        "保存したシナリオはまだありません。",
+    ),
+    ("Name", "名前"),
+    ("Tags", "タグ"),
+    ("Setup", "設定"),
+    ("Load", "読み込む"),
+    ("Duplicate", "複製"),
+    // Sweep
+    (
+        "Choose a number of simulations.",
+        "シミュレーション回数を指定してください。",
+    ),
+    (
+        "None of the goals are available on these banners.",
+        "これらのガチャで達成できる目標がありません。",
+    ),
+    ("Goals:", "目標:"),
+    ("Copies of each: ", "各目標の体数: "),
+    ("Focus sizes from ", "ピックアップ人数 "),
+    (" to ", " ～ "),
+    (" for ", " 対象: "),
+    ("every color", "全色"),
+    ("Rates:", "排出率:"),
+    ("Focus charges:", "ピックアップチャージ:"),
+    ("Off", "なし"),
+    ("On", "あり"),
+    ("Simulations per cell: ", "セルごとのシミュレーション回数: "),
+    ("Run {count} cells", "{count}セルを実行"),
+    ("Mean: {mean}", "平均: {mean}"),
+    ("Show: ", "表示: "),
+    ("{pct}th percentile", "{pct}パーセンタイル"),
+    ("Export CSV", "CSVを書き出す"),
+    (
+        "{done} of {count} cells done",
+        "{count}セル中{done}セル完了",
+    ),
+    ("Rates", "排出率"),
+    ("Focus charges", "ピックアップチャージ"),
+    ("{size} each", "各{size}"),
+    (
+        "Orbs needed for each goal over a range of banners. Banners start from \
+         the current one: {banner}",
+        "さまざまなガチャでの各目標の必要オーブ数です。ガチャは現在の設定を基にします: {banner}",
+    ),
+    // Sensitivity
+    ("Current setup", "現在の設定"),
+    ("One more copy", "1体多く"),
+    ("One fewer copy", "1体少なく"),
+    (
+        "One more {color} focus unit",
+        "{color}のピックアップを1人追加",
+    ),
+    (
+        "One fewer {color} focus unit",
+        "{color}のピックアップを1人削減",
+    ),
+    ("Focus charges on", "ピックアップチャージあり"),
+    ("Focus charges off", "ピックアップチャージなし"),
+    ("Change", "変更"),
+    (
+        "How the cost of the current setup changes with one more or one fewer copy, \
+         focus unit of each color, or with focus charges switched. Every variant \
+         uses the same random numbers, so the differences are mostly due to the \
+         change itself.",
+        "現在の設定から、必要な体数や各色のピックアップ人数を1つ増減したり、\
+         ピックアップチャージを切り替えたりしたときのコストの変化です。\
+         すべての変更で同じ乱数を使うので、差はほぼ変更そのものによるものです。",
+    ),
+    (
+        "Simulations per variant: ",
+        "変更ごとのシミュレーション回数: ",
+    ),
+    ("{done} of {count} variants done", "{count}件中{done}件完了"),
+    ("Results for: {setup}", "結果の対象: {setup}"),
+];
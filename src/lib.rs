@@ -5,32 +5,75 @@ use seed::prelude::*;
 use std::convert::TryFrom;
 use std::fmt;
 
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use serde::{Deserialize, Serialize};
 
 mod banner;
-use banner::Banner;
+pub use banner::Banner;
 
 mod goal;
-use goal::{Goal, GoalKind, GoalPart, GoalPreset};
+pub use goal::{Goal, GoalPreset};
+use goal::{GoalExpr, GoalKind, GoalPart};
 
 mod results;
+use results::{GraphState, GraphView};
 
 mod sim;
-use sim::Sim;
+pub use sim::Sim;
 
 mod weighted_choice;
 
 mod stats;
 
 mod counter;
-use counter::Counter;
+
+mod histogram;
+pub use histogram::Histogram;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod parallel;
+
+mod export;
+
+mod batch;
+
+mod history;
+use history::{HistoryEntry, Session};
+
+mod storage;
+use storage::StoreError;
+
+mod scenarios;
+use scenarios::{Scenario, ScenarioLibrary};
+
+mod undo;
+use undo::{Snapshot, UndoStack};
+
+pub mod sweep;
+use sweep::SweepState;
+
+mod sensitivity;
+use sensitivity::SensitivityState;
+
+mod precision;
+use precision::PrecisionState;
+
+mod validation;
+
+mod stepper;
 
 mod subpages;
 
 mod query_string;
 
+mod i18n;
+use i18n::{tr, Language};
+
+mod theme;
+use theme::Theme;
+
 // Model
 
 #[repr(u8)]
@@ -42,9 +85,23 @@ pub enum Color {
     Colorless,
 }
 
+impl Color {
+    /// The English name of the color, which stays the same whatever the
+    /// language of the interface.
+    pub fn name(self) -> &'static str {
+        use Color::*;
+        match self {
+            Red => "Red",
+            Blue => "Blue",
+            Green => "Green",
+            Colorless => "Colorless",
+        }
+    }
+}
+
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        f.write_str(tr(self.name()))
     }
 }
 
@@ -73,6 +130,20 @@ pub enum Pool {
     Threestar,
 }
 
+impl fmt::Display for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Pool::*;
+        let s = match *self {
+            Focus => "5* focus",
+            Fivestar => "general 5*",
+            FourstarFocus => "4* focus",
+            Fourstar => "general 4*",
+            Threestar => "general 3*",
+        };
+        f.write_str(tr(s))
+    }
+}
+
 impl TryFrom<u8> for Pool {
     type Error = ();
 
@@ -95,6 +166,9 @@ pub enum Page {
     Main,
     Help,
     Changelog,
+    Scenarios,
+    Sweep,
+    Sensitivity,
 }
 
 impl Default for Page {
@@ -107,15 +181,37 @@ impl Default for Page {
 #[derive(Default, Debug)]
 struct Model {
     /// The data that the simulation has gathered so far.
-    pub data: Counter,
+    pub data: Histogram,
     /// The parameters of the current banner.
     pub banner: Banner,
     /// The paremeters of the current goal.
     pub goal: Goal,
     /// The current page that the application is on.
     pub curr_page: Page,
-    /// The point on the graph that the user has chose to highlight.
-    pub graph_highlight: Option<f32>,
+    /// The display settings and interaction state of the results graph.
+    pub graph: GraphState,
+    /// The orb count used for the chance-of-exceeding row of the summary table.
+    pub summary_threshold: Option<u32>,
+    /// Past runs, newest first.
+    pub history: Vec<HistoryEntry>,
+    /// The timestamp of the history entry that the current results belong to.
+    pub current_run: Option<f64>,
+    /// Saved banner and goal setups.
+    pub scenario_library: ScenarioLibrary,
+    /// Earlier and undone versions of the banner, goal, and results.
+    pub undo: UndoStack,
+    /// Settings and results of sweeps over banners and goals.
+    pub sweep: SweepState,
+    /// Comparisons of the current setup with small changes to it.
+    pub sensitivity: SensitivityState,
+    /// Settings and progress of running until the results are precise enough,
+    /// and the precision that the current results reached.
+    pub precision: PrecisionState,
+    /// The chosen color theme.
+    pub theme: Theme,
+    /// Whether the user has been told that the results couldn't be saved, so
+    /// that they aren't told again every time.
+    pub store_error_shown: bool,
 }
 
 // Update
@@ -154,8 +250,24 @@ pub enum Msg {
     GoalPartQuantityChange { index: usize, quantity: u8 },
     /// Add a new individual unit target.
     GoalPartAdd { color: Color, quantity: u8 },
+    /// Change which pool a part of a custom goal is from.
+    GoalPartPoolChange { index: usize, pool: Pool },
     /// Change whether the individual targets all need to happen or just one.
     GoalKindChange { kind: GoalKind },
+    /// Convert the current goal into a nested goal.
+    GoalMakeNested,
+    /// Change the kind of the group at the given path in a nested goal.
+    GoalNodeKindChange { path: Vec<usize>, kind: GoalKind },
+    /// Replace the unit at the given path in a nested goal.
+    GoalNodePartChange {
+        path: Vec<usize>,
+        part: GoalPart,
+        unit: u8,
+    },
+    /// Add a unit or a group to the group at the given path in a nested goal.
+    GoalNodeAdd { path: Vec<usize>, group: bool },
+    /// Remove the unit or group at the given path in a nested goal.
+    GoalNodeRemove { path: Vec<usize> },
     /// Replace the goal with a new one.
     GoalSet { goal: Goal },
     /// Change which page of the application is open.
@@ -163,11 +275,213 @@ pub enum Msg {
     /// Generate a permalink that saves the application's paremeters.
     Permalink,
     /// Highlight a point on the graph.
-    GraphHighlight { frac: f32 },
+    GraphHighlight { x: f32 },
+    /// Show a label at the point on the graph under the cursor, or stop showing
+    /// it when the cursor leaves the graph.
+    GraphHover { x: Option<f32> },
+    /// Move the label to the point on the graph under a finger that is
+    /// scrubbing along it, or stop scrubbing without choosing a point.
+    GraphScrub { x: Option<f32> },
+    /// Start a possible drag across the graph to select a range to zoom in on.
+    GraphDragStart { x: f32 },
+    /// Zoom in on a range of the graph's horizontal axis, or reset the zoom.
+    GraphZoom { range: Option<(f32, f32)> },
+    /// Change whether the graph's orbs axis uses a logarithmic scale.
+    GraphLogScaleToggle,
+    /// Change how the results are displayed on the graph.
+    GraphViewChange { view: GraphView },
+    /// Download the graph as an SVG image.
+    ExportSvg,
+    /// Download the graph as a PNG image.
+    ExportPng,
+    /// Change the orb count used for the chance-of-exceeding statistic.
+    SummaryThresholdChange { orbs: Option<u32> },
+    /// Reopen the settings and results of a past run.
+    HistoryOpen { index: usize },
+    /// Remove a past run from the history.
+    HistoryDelete { index: usize },
+    /// Change the name to save the current setup under.
+    ScenarioNewNameChange { name: String },
+    /// Change the tags to save the current setup with.
+    ScenarioNewTagsChange { tags: String },
+    /// Save the current banner and goal as a new scenario.
+    ScenarioSave,
+    /// Open the banner and goal of a saved scenario.
+    ScenarioLoad { index: usize },
+    /// Change the name of a saved scenario.
+    ScenarioRename { index: usize, name: String },
+    /// Change the tags of a saved scenario.
+    ScenarioTagsChange { index: usize, tags: Vec<String> },
+    /// Add a copy of a saved scenario.
+    ScenarioDuplicate { index: usize },
+    /// Remove a saved scenario.
+    ScenarioDelete { index: usize },
+    /// Change which saved scenarios are shown.
+    ScenarioFilterChange { filter: String },
+    /// Read scenarios to import from a file.
+    ScenarioImportFile { file: web_sys::File },
+    /// Add scenarios from their JSON representation.
+    ScenarioImport { json: String },
+    /// Download all saved scenarios as a JSON file.
+    ScenarioExport,
+    /// Go back to the banner, goal, and results from before the last change.
+    Undo,
+    /// Reapply the last undone change.
+    Redo,
+    /// Add or remove a goal from the sweep.
+    SweepPresetToggle { preset: GoalPreset },
+    /// Change the number of copies for the sweep's goals.
+    SweepCopiesChange { copies: u8 },
+    /// Change the range of focus sizes in the sweep.
+    SweepSizeRangeChange { min: i8, max: i8 },
+    /// Change which color's focus size the sweep changes.
+    SweepSizeColorChange { color: Option<Color> },
+    /// Add or remove starting rates from the sweep.
+    SweepRatesToggle { rates: (u8, u8) },
+    /// Add or remove a focus charge setting from the sweep.
+    SweepFocusChargesToggle { focus_charges: bool },
+    /// Add or remove a percentile from the sweep's results.
+    SweepPercentileToggle { percentile: u8 },
+    /// Change the number of simulations for each cell of the sweep.
+    SweepTrialsChange { trials: u32 },
+    /// Start running the sweep.
+    SweepStart,
+    /// Run some more of the sweep.
+    SweepStep,
+    /// Stop running the sweep.
+    SweepStop,
+    /// Change which percentile the sweep's results table shows.
+    SweepShownPercentileChange { index: usize },
+    /// Download the sweep's results as a CSV file.
+    SweepExport,
+    /// Change the number of simulations for each variant in the sensitivity
+    /// analysis.
+    SensitivityTrialsChange { trials: u32 },
+    /// Start comparing the current setup with small changes to it.
+    SensitivityStart,
+    /// Run some more of the sensitivity analysis.
+    SensitivityStep,
+    /// Stop running the sensitivity analysis.
+    SensitivityStop,
+    /// Change how precise a run needs to be before it stops, as a percentage of
+    /// each percentile's value.
+    PrecisionToleranceChange { tolerance: f32 },
+    /// Add or remove a percentile that needs to be precise.
+    PrecisionPercentileToggle { percentile: u8 },
+    /// Change the most samples to gather while running to precision.
+    PrecisionMaxSamplesChange { samples: u64 },
+    /// Start gathering data until the results are precise enough.
+    PrecisionStart,
+    /// Gather some more data for the run to precision.
+    PrecisionStep,
+    /// Stop running to precision.
+    PrecisionStop,
+    /// Change the language of the interface.
+    LanguageChange { language: Language },
+    /// Change the color theme.
+    ThemeChange { theme: Theme },
+}
+
+impl Msg {
+    /// Whether handling this message can change the banner, goal, or results,
+    /// which means that they need to be saved again.
+    fn changes_session(&self) -> bool {
+        use Msg::*;
+        matches!(
+            self,
+            Run | BannerFocusSizeChange { .. }
+                | BannerFourstarFocusChange { .. }
+                | BannerRateChange { .. }
+                | BannerFocusChargesToggle
+                | BannerSet { .. }
+                | GoalPresetChange { .. }
+                | GoalPresetQuantityChange { .. }
+                | GoalMakeCustom
+                | GoalPartColorChange { .. }
+                | GoalPartQuantityChange { .. }
+                | GoalPartAdd { .. }
+                | GoalPartPoolChange { .. }
+                | GoalKindChange { .. }
+                | GoalMakeNested
+                | GoalNodeKindChange { .. }
+                | GoalNodePartChange { .. }
+                | GoalNodeAdd { .. }
+                | GoalNodeRemove { .. }
+                | GoalSet { .. }
+                | HistoryOpen { .. }
+                | Undo
+                | Redo
+        )
+    }
+
+    /// Whether handling this message can change the banner or goal, which
+    /// should be recorded so that it can be undone.
+    fn edits_setup(&self) -> bool {
+        use Msg::*;
+        matches!(
+            self,
+            BannerFocusSizeChange { .. }
+                | BannerFourstarFocusChange { .. }
+                | BannerRateChange { .. }
+                | BannerFocusChargesToggle
+                | BannerSet { .. }
+                | GoalPresetChange { .. }
+                | GoalPresetQuantityChange { .. }
+                | GoalMakeCustom
+                | GoalPartColorChange { .. }
+                | GoalPartQuantityChange { .. }
+                | GoalPartAdd { .. }
+                | GoalPartPoolChange { .. }
+                | GoalKindChange { .. }
+                | GoalMakeNested
+                | GoalNodeKindChange { .. }
+                | GoalNodePartChange { .. }
+                | GoalNodeAdd { .. }
+                | GoalNodeRemove { .. }
+                | GoalSet { .. }
+                | HistoryOpen { .. }
+        )
+    }
+}
+
+/// Captures the parts of the model that undo and redo restore.
+fn snapshot(model: &Model) -> Snapshot {
+    Snapshot {
+        banner: model.banner,
+        goal: model.goal.clone(),
+        data: model.data.clone(),
+        current_run: model.current_run,
+        precision: model.precision.achieved,
+    }
+}
+
+/// Replaces the banner, goal, and results with ones from a snapshot.
+fn restore(model: &mut Model, snapshot: Snapshot) {
+    model.banner = snapshot.banner;
+    model.goal = snapshot.goal;
+    model.data = snapshot.data;
+    model.current_run = snapshot.current_run;
+    model.precision.achieved = snapshot.precision;
+    model.graph.zoom = None;
+    model.graph.highlight = None;
 }
 
 /// Update model with the given message.
 fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    let changes_session = msg.changes_session();
+    let before = if msg.edits_setup() {
+        Some(snapshot(model))
+    } else {
+        None
+    };
+    // Anything that changes the results other than the run to precision
+    // itself leaves them with a different precision than it recorded. Undo
+    // and redo bring back the precision along with the results.
+    if matches!(msg, Msg::Undo | Msg::Redo) {
+        model.precision.stop();
+    } else if changes_session {
+        model.precision.reset();
+    }
     match msg {
         Msg::Null => {
             orders.skip();
@@ -208,27 +522,22 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             model.data.clear();
         }
         Msg::Run => {
-            if !model.goal.is_available(&model.banner) {
+            if validation::has_errors(&validation::validate(&model.goal, &model.banner)) {
                 return;
             }
+            if model.data.is_empty() {
+                model.graph.zoom = None;
+            }
             let mut sim = Sim::new(model.banner, model.goal.clone());
-            let mut limit = 100;
-            let perf = seed::window().performance().unwrap();
-            let start = perf.now();
-
-            // Exponential increase with a loose target of 500 ms of calculation.
-            // Time per simulation varies wildly depending on device performance
-            // and sim parameters, so it starts with a very low number and goes
-            // from there.
-            while perf.now() - start < 250.0 {
-                for _ in 0..limit {
-                    let result = sim.roll_until_goal();
-                    model.data[result] += 1;
+            let data = &mut model.data;
+            batch::run_for(batch::RUN_MS, |chunk_size| {
+                for _ in 0..chunk_size {
+                    data.record(sim.roll_until_goal());
                 }
-                limit *= 2;
-            }
+                true
+            });
 
-            model.graph_highlight = None;
+            record_results(model);
         }
         Msg::GoalPresetChange { preset } => {
             let count = if let Goal::Preset(_, count) = model.goal {
@@ -236,10 +545,8 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             } else {
                 1
             };
-            if preset.is_available(&model.banner) {
-                model.goal = Goal::Preset(preset, count);
-                model.data.clear();
-            }
+            model.goal = Goal::Preset(preset, count);
+            model.data.clear();
         }
         Msg::GoalPresetQuantityChange { quantity } => {
             if let Goal::Preset(_, count) = &mut model.goal {
@@ -254,12 +561,7 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             }
         }
         Msg::GoalMakeCustom => {
-            let mut custom = model.goal.as_custom(&model.banner);
-            // 4* focuses in custom goals are not supported
-            for part in &mut custom.goals {
-                part.four_star = false;
-            }
-            model.goal = Goal::Custom(custom);
+            model.goal = Goal::Custom(model.goal.as_custom(&model.banner));
             model.data.clear();
         }
         Msg::GoalPartQuantityChange { index, quantity } => {
@@ -277,17 +579,66 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 custom_goal.goals.push(GoalPart {
                     unit_color: color,
                     num_copies: quantity,
-                    four_star: false,
+                    pool: Pool::Focus,
                 });
                 model.data.clear();
             }
         }
+        Msg::GoalPartPoolChange { index, pool } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                if let Some(part) = custom_goal.goals.get_mut(index) {
+                    part.pool = pool;
+                    model.data.clear();
+                }
+            }
+        }
         Msg::GoalKindChange { kind } => {
             if let Goal::Custom(custom_goal) = &mut model.goal {
                 custom_goal.kind = kind;
                 model.data.clear();
             }
         }
+        Msg::GoalMakeNested => {
+            model.goal = Goal::Expr(model.goal.as_expr(&model.banner));
+            model.data.clear();
+        }
+        Msg::GoalNodeKindChange { path, kind } => {
+            if let Goal::Expr(expr) = &mut model.goal {
+                if let Some(GoalExpr::Group {
+                    kind: node_kind, ..
+                }) = expr.node_mut(&path)
+                {
+                    *node_kind = kind;
+                    model.data.clear();
+                }
+            }
+        }
+        Msg::GoalNodePartChange { path, part, unit } => {
+            if let Goal::Expr(expr) = &mut model.goal {
+                if let Some(node @ GoalExpr::Part { .. }) = expr.node_mut(&path) {
+                    *node = GoalExpr::Part { part, unit };
+                    model.data.clear();
+                }
+            }
+        }
+        Msg::GoalNodeAdd { path, group } => {
+            if let Goal::Expr(expr) = &mut model.goal {
+                if let Some(GoalExpr::Group { children, .. }) = expr.node_mut(&path) {
+                    children.push(if group {
+                        GoalExpr::new_group()
+                    } else {
+                        GoalExpr::default()
+                    });
+                    model.data.clear();
+                }
+            }
+        }
+        Msg::GoalNodeRemove { path } => {
+            if let Goal::Expr(expr) = &mut model.goal {
+                expr.remove_node(&path);
+                model.data.clear();
+            }
+        }
         Msg::GoalSet { goal } => {
             model.goal = goal;
             model.data.clear();
@@ -297,18 +648,390 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         }
         Msg::Permalink => {
             let url = seed::Url::new(vec![""]).search(&format!(
-                "v=3&banner={}&goal={}&run=1",
+                "v=3&banner={}&goal={}&run=1&lang={}",
                 base64::encode(&bincode::serialize(&model.banner).unwrap()),
-                base64::encode(&bincode::serialize(&model.goal).unwrap())
+                base64::encode(&bincode::serialize(&model.goal).unwrap()),
+                i18n::language().code()
             ));
             seed::push_route(url);
         }
-        Msg::GraphHighlight { frac } => {
-            model.graph_highlight = Some(frac);
+        Msg::GraphHighlight { x } => {
+            model.graph.highlight = Some(x);
+            model.graph.drag_start = None;
+            model.graph.scrub = None;
+        }
+        Msg::GraphHover { x } => {
+            if model.graph.hover == x {
+                orders.skip();
+            }
+            model.graph.hover = x;
+            if x.is_none() {
+                model.graph.drag_start = None;
+            }
+        }
+        Msg::GraphScrub { x } => {
+            if model.graph.scrub == x {
+                orders.skip();
+            }
+            model.graph.scrub = x;
+        }
+        Msg::GraphDragStart { x } => {
+            model.graph.drag_start = Some(x);
+        }
+        Msg::GraphZoom { range } => {
+            model.graph.zoom = range;
+            model.graph.highlight = None;
+            model.graph.drag_start = None;
+        }
+        Msg::GraphLogScaleToggle => {
+            model.graph.log_scale = !model.graph.log_scale;
+        }
+        Msg::GraphViewChange { view } => {
+            model.graph.view = view;
+            model.graph.zoom = None;
+            model.graph.highlight = None;
+        }
+        Msg::ExportSvg => {
+            orders.skip();
+            if export::download_svg(&export_title(model), model.theme).is_err() {
+                alert(tr("Could not create the image."));
+            }
+        }
+        Msg::ExportPng => {
+            orders.skip();
+            if export::download_png(&export_title(model), model.theme).is_err() {
+                alert(tr("Could not create the image."));
+            }
+        }
+        Msg::SummaryThresholdChange { orbs } => {
+            model.summary_threshold = orbs;
+        }
+        Msg::HistoryOpen { index } => {
+            if let Some(entry) = model.history.get(index) {
+                model.banner = entry.session.banner;
+                model.goal = entry.session.goal.clone();
+                model.data = entry.session.data.clone();
+                model.precision.achieved = entry.session.precision;
+                model.current_run = Some(entry.timestamp);
+                model.graph.zoom = None;
+                model.graph.highlight = None;
+            }
+        }
+        Msg::HistoryDelete { index } => {
+            if index < model.history.len() {
+                let entry = model.history.remove(index);
+                if model.current_run == Some(entry.timestamp) {
+                    model.current_run = None;
+                }
+                let result = history::save_history(&mut model.history);
+                report_store_error(model, result);
+            }
+        }
+        Msg::ScenarioNewNameChange { name } => {
+            model.scenario_library.new_name = name;
+        }
+        Msg::ScenarioNewTagsChange { tags } => {
+            model.scenario_library.new_tags = tags;
+        }
+        Msg::ScenarioSave => {
+            let library = &mut model.scenario_library;
+            library.scenarios.push(Scenario {
+                name: library.new_name.trim().to_string(),
+                tags: scenarios::parse_tags(&library.new_tags),
+                banner: model.banner,
+                goal: model.goal.clone(),
+            });
+            library.new_name.clear();
+            library.new_tags.clear();
+            scenarios::save(&library.scenarios);
+        }
+        Msg::ScenarioLoad { index } => {
+            if let Some(scenario) = model.scenario_library.scenarios.get(index) {
+                orders
+                    .send_msg(Msg::BannerSet {
+                        banner: scenario.banner,
+                    })
+                    .send_msg(Msg::GoalSet {
+                        goal: scenario.goal.clone(),
+                    });
+                model.curr_page = Page::Main;
+                seed::push_route(vec![""]);
+            }
+        }
+        Msg::ScenarioRename { index, name } => {
+            if let Some(scenario) = model.scenario_library.scenarios.get_mut(index) {
+                scenario.name = name;
+                scenarios::save(&model.scenario_library.scenarios);
+            }
+        }
+        Msg::ScenarioTagsChange { index, tags } => {
+            if let Some(scenario) = model.scenario_library.scenarios.get_mut(index) {
+                scenario.tags = tags;
+                scenarios::save(&model.scenario_library.scenarios);
+            }
+        }
+        Msg::ScenarioDuplicate { index } => {
+            let scenarios = &mut model.scenario_library.scenarios;
+            if let Some(scenario) = scenarios.get(index) {
+                let mut copy = scenario.clone();
+                copy.name = format!("{} (copy)", copy.name);
+                scenarios.insert(index + 1, copy);
+                scenarios::save(scenarios);
+            }
+        }
+        Msg::ScenarioDelete { index } => {
+            let scenarios = &mut model.scenario_library.scenarios;
+            if index < scenarios.len() {
+                scenarios.remove(index);
+                scenarios::save(scenarios);
+            }
+        }
+        Msg::ScenarioFilterChange { filter } => {
+            model.scenario_library.filter = filter;
+        }
+        Msg::ScenarioImportFile { file } => {
+            orders.skip().perform_cmd(async move {
+                match scenarios::read_file(file).await {
+                    Ok(json) => Ok(Msg::ScenarioImport { json }),
+                    Err(_) => Err(Msg::Alert {
+                        message: tr("Could not read the file.").into(),
+                    }),
+                }
+            });
+        }
+        Msg::ScenarioImport { json } => match scenarios::from_json(&json) {
+            Ok(imported) => {
+                let scenarios = &mut model.scenario_library.scenarios;
+                scenarios.extend(imported);
+                scenarios::save(scenarios);
+            }
+            Err(_) => alert(tr("The file does not contain valid scenarios.")),
+        },
+        Msg::ScenarioExport => {
+            orders.skip();
+            let json = scenarios::to_json(&model.scenario_library.scenarios);
+            if export::download_text(&json, "application/json", "fehstatsim_scenarios.json")
+                .is_err()
+            {
+                alert(tr("Could not export the scenarios."));
+            }
+        }
+        Msg::Undo => {
+            if let Some(previous) = model.undo.undo(snapshot(model)) {
+                restore(model, previous);
+            }
+        }
+        Msg::Redo => {
+            if let Some(next) = model.undo.redo(snapshot(model)) {
+                restore(model, next);
+            }
+        }
+        Msg::SweepPresetToggle { preset } => {
+            sweep::toggle(
+                &mut model.sweep.settings.presets,
+                preset,
+                GoalPreset::iter(),
+            );
+        }
+        Msg::SweepCopiesChange { copies } => {
+            model.sweep.settings.copies = copies;
+        }
+        Msg::SweepSizeRangeChange { min, max } => {
+            model.sweep.settings.min_size = min;
+            model.sweep.settings.max_size = max;
+        }
+        Msg::SweepSizeColorChange { color } => {
+            model.sweep.settings.size_color = color;
+        }
+        Msg::SweepRatesToggle { rates } => {
+            let options = banner::STARTING_RATES.iter().map(|&(rates, _)| rates);
+            sweep::toggle(&mut model.sweep.settings.rates, rates, options);
+        }
+        Msg::SweepFocusChargesToggle { focus_charges } => {
+            let options = vec![false, true];
+            sweep::toggle(
+                &mut model.sweep.settings.focus_charges,
+                focus_charges,
+                options,
+            );
+        }
+        Msg::SweepPercentileToggle { percentile } => {
+            let options = sweep::PERCENTILE_OPTIONS.iter().copied();
+            sweep::toggle(&mut model.sweep.settings.percentiles, percentile, options);
+        }
+        Msg::SweepTrialsChange { trials } => {
+            model.sweep.settings.trials = trials;
+        }
+        Msg::SweepStart => {
+            if model.sweep.settings.problem(&model.banner).is_none() {
+                model.sweep.start(&model.banner);
+                orders.after_next_render(|_| Msg::SweepStep);
+            }
+        }
+        Msg::SweepStep => {
+            if model.sweep.step() {
+                orders.after_next_render(|_| Msg::SweepStep);
+            }
+        }
+        Msg::SweepStop => {
+            model.sweep.run.stop();
+        }
+        Msg::SweepShownPercentileChange { index } => {
+            model.sweep.shown_percentile = index;
+        }
+        Msg::SweepExport => {
+            orders.skip();
+            let csv = sweep::to_csv(&model.sweep.run_settings, &model.sweep.results);
+            if export::download_text(&csv, "text/csv", "fehstatsim_sweep.csv").is_err() {
+                alert(tr("Could not export the sweep results."));
+            }
+        }
+        Msg::SensitivityTrialsChange { trials } => {
+            model.sensitivity.trials = trials;
+        }
+        Msg::SensitivityStart => {
+            if !validation::has_errors(&validation::validate(&model.goal, &model.banner)) {
+                model.sensitivity.start(&model.banner, &model.goal);
+                orders.after_next_render(|_| Msg::SensitivityStep);
+            }
+        }
+        Msg::SensitivityStep => {
+            if model.sensitivity.run.step() {
+                orders.after_next_render(|_| Msg::SensitivityStep);
+            }
+        }
+        Msg::SensitivityStop => {
+            model.sensitivity.run.stop();
+        }
+        Msg::PrecisionToleranceChange { tolerance } => {
+            model.precision.settings.tolerance = tolerance;
+        }
+        Msg::PrecisionPercentileToggle { percentile } => {
+            sweep::toggle(
+                &mut model.precision.settings.percentiles,
+                percentile,
+                sweep::PERCENTILE_OPTIONS.iter().copied(),
+            );
+        }
+        Msg::PrecisionMaxSamplesChange { samples } => {
+            model.precision.settings.max_samples = samples;
+        }
+        Msg::PrecisionStart => {
+            if !validation::has_errors(&validation::validate(&model.goal, &model.banner))
+                && model.precision.settings.problem().is_none()
+            {
+                if model.data.is_empty() {
+                    model.graph.zoom = None;
+                }
+                model
+                    .precision
+                    .start(model.banner, model.goal.clone(), &model.data);
+                orders.after_next_render(|_| Msg::PrecisionStep);
+            }
+        }
+        Msg::PrecisionStep => {
+            if !model.precision.is_running() {
+                // The run was stopped after this step was queued.
+            } else if model.precision.step(&mut model.data) {
+                orders.after_next_render(|_| Msg::PrecisionStep);
+            } else {
+                record_results(model);
+                save_session(model);
+            }
+        }
+        Msg::LanguageChange { language } => {
+            i18n::apply_language(language);
+            i18n::save_language(language);
+            i18n::show_language_in_url(language);
+        }
+        Msg::ThemeChange { theme } => {
+            model.theme = theme;
+            theme::apply_theme(theme);
+            theme::save_theme(theme);
+        }
+        Msg::PrecisionStop => {
+            if model.precision.is_running() {
+                model.precision.stop();
+                if !model.data.is_empty() {
+                    record_results(model);
+                    save_session(model);
+                }
+            }
+        }
+    }
+
+    if let Some(before) = before {
+        if before.banner != model.banner
+            || before.goal != model.goal
+            || before.current_run != model.current_run
+        {
+            model.undo.record(before);
+        }
+    }
+
+    if changes_session {
+        save_session(model);
+    }
+}
+
+/// Saves the banner, goal, and results so that they are still there the next
+/// time the app is opened.
+fn save_session(model: &mut Model) {
+    // Changing the banner or goal throws out the results, so the next run
+    // starts a new history entry.
+    if model.data.is_empty() {
+        model.current_run = None;
+    }
+    let result = history::save_session(
+        model.current_run,
+        &model.banner,
+        &model.goal,
+        &model.data,
+        model.precision.achieved,
+        &mut model.history,
+    );
+    report_store_error(model, result);
+}
+
+/// Tells the user the first time that the results couldn't be saved. Nothing
+/// is said when the browser doesn't allow saving at all, since that is the
+/// user's choice.
+fn report_store_error(model: &mut Model, result: Result<(), StoreError>) {
+    if let Err(error) = result {
+        if error != StoreError::Unavailable && !model.store_error_shown {
+            model.store_error_shown = true;
+            alert(tr(
+                "The results could not be saved in the browser, so they will be lost \
+                 when the page is closed.",
+            ));
         }
     }
 }
 
+/// Records the current results in the history, after they have had more data
+/// added to them.
+fn record_results(model: &mut Model) {
+    model.graph.highlight = None;
+
+    model.current_run = Some(history::record_run(
+        &mut model.history,
+        model.current_run,
+        Session {
+            banner: model.banner,
+            goal: model.goal.clone(),
+            data: model.data.clone(),
+            precision: model.precision.achieved,
+        },
+    ));
+    let result = history::save_history(&mut model.history);
+    report_store_error(model, result);
+}
+
+/// Title for exported images, describing what the results are for.
+fn export_title(model: &Model) -> String {
+    format!("{} - {}", model.goal, model.banner)
+}
+
 // View
 
 /// Display the current state.
@@ -317,6 +1040,13 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
         Page::Main => main_page(model),
         Page::Help => subpages::help(),
         Page::Changelog => subpages::changelog(),
+        Page::Scenarios => {
+            scenarios::scenarios_page(&model.scenario_library, &model.banner, &model.goal)
+        }
+        Page::Sweep => sweep::sweep_page(&model.sweep, &model.banner),
+        Page::Sensitivity => {
+            sensitivity::sensitivity_page(&model.sensitivity, &model.banner, &model.goal)
+        }
     }
 }
 
@@ -326,25 +1056,50 @@ fn main_page(model: &Model) -> Vec<Node<Msg>> {
         header![
             class!["no-select"],
             a![
-                "How to use",
+                tr("How to use"),
                 attrs! [
                     At::Href => "/help";
                 ],
             ],
+            " | ",
+            a![
+                tr("Scenarios"),
+                attrs![
+                    At::Href => "/scenarios";
+                ],
+            ],
+            " | ",
+            a![
+                tr("Sweep"),
+                attrs![
+                    At::Href => "/sweep";
+                ],
+            ],
+            " | ",
+            a![
+                tr("Sensitivity"),
+                attrs![
+                    At::Href => "/sensitivity";
+                ],
+            ],
             " | v0.3.1 ",
             a![
-                "Changelog",
+                tr("Changelog"),
                 attrs![
                     At::Href => "/changelog";
                 ],
             ],
             " | ",
             a![
-                "Contact",
+                tr("Contact"),
                 attrs![
                     At::Href => "https://www.reddit.com/message/compose?to=minno&subject=fehstatsim%20site%20help";
                 ]
-            ]
+            ],
+            " | ",
+            i18n::language_select(),
+            " ",
+            theme::theme_select(model.theme),
         ],
         div![
             class!["no-select"],
@@ -358,16 +1113,46 @@ fn main_page(model: &Model) -> Vec<Node<Msg>> {
                 ],
                 button![
                     simple_ev(Ev::Click, Msg::Run),
-                    if !model.goal.is_available(&model.banner) {
+                    if validation::has_errors(&validation::validate(&model.goal, &model.banner)) {
+                        attrs![
+                            At::Disabled => true;
+                            At::Title => tr("The goal can't be completed on this banner");
+                        ]
+                    } else {
+                        attrs![]
+                    },
+                    tr(if model.data.is_empty() { "Run" } else { "More" })
+                ],
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::Undo),
+                    attrs![At::Title => tr("Undo (Ctrl+Z)")],
+                    if !model.undo.can_undo() {
                         attrs![At::Disabled => true]
                     } else {
                         attrs![]
                     },
-                    if model.data.is_empty() { "Run" } else { "More" }
+                    tr("Undo")
+                ],
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::Redo),
+                    attrs![At::Title => tr("Redo (Ctrl+Y)")],
+                    if !model.undo.can_redo() {
+                        attrs![At::Disabled => true]
+                    } else {
+                        attrs![]
+                    },
+                    tr("Redo")
                 ],
                 permalink(),
             ],
-            results::results(&model.data, model.graph_highlight),
+            precision::precision_controls(
+                &model.precision,
+                !validation::has_errors(&validation::validate(&model.goal, &model.banner)),
+            ),
+            results::results(&model.data, &model.graph, model.summary_threshold),
+            history::history_list(&model.history, model.current_run),
         ],
     ]
 }
@@ -432,6 +1217,9 @@ fn routes(url: seed::Url) -> Option<Msg> {
     messages.push(match url.path.get(0).map(String::as_str) {
         Some("help") => Msg::PageChange(Page::Help),
         Some("changelog") => Msg::PageChange(Page::Changelog),
+        Some("scenarios") => Msg::PageChange(Page::Scenarios),
+        Some("sweep") => Msg::PageChange(Page::Sweep),
+        Some("sensitivity") => Msg::PageChange(Page::Sensitivity),
         _ => Msg::PageChange(Page::Main),
     });
 
@@ -459,11 +1247,11 @@ fn routes(url: seed::Url) -> Option<Msg> {
 
     if query_string::get(&url, "v").is_some() && query_string::get(&url, "v") != Some("3") {
         Some(Msg::Alert {
-            message: "The permalink format has changed, please update your link.".into(),
+            message: tr("The permalink format has changed, please update your link.").into(),
         })
     } else if invalid_query_string {
         Some(Msg::Alert {
-            message: "Invalid permalink".into(),
+            message: tr("Invalid permalink").into(),
         })
     } else if messages.is_empty() {
         None
@@ -472,14 +1260,76 @@ fn routes(url: seed::Url) -> Option<Msg> {
     }
 }
 
+/// Keyboard shortcuts for undo and redo. Text fields are left alone so that
+/// they keep their own undo behavior.
+fn window_events(_model: &Model) -> Vec<EventHandler<Msg>> {
+    vec![keyboard_ev(Ev::KeyDown, |event| {
+        let in_text_field = event
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .map(|element| element.tag_name() == "INPUT" || element.tag_name() == "TEXTAREA")
+            .unwrap_or(false);
+        if in_text_field || !(event.ctrl_key() || event.meta_key()) {
+            return Msg::Null;
+        }
+        let msg = match event.key().to_lowercase().as_str() {
+            "z" if event.shift_key() => Msg::Redo,
+            "z" => Msg::Undo,
+            "y" => Msg::Redo,
+            _ => return Msg::Null,
+        };
+        event.prevent_default();
+        msg
+    })]
+}
+
 #[wasm_bindgen]
 extern "C" {
     fn alert(text: &str);
 }
 
+/// Sets up the initial state, restoring the last session if the app wasn't
+/// opened with a permalink. The language comes from the URL if it has one,
+/// and otherwise from the last time the app was used or the browser's
+/// settings. The theme is the one chosen the last time the app was used.
+fn after_mount(url: seed::Url, _orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let language = query_string::get(&url, "lang")
+        .and_then(Language::from_code)
+        .or_else(i18n::load_language)
+        .or_else(i18n::browser_language)
+        .unwrap_or_default();
+    i18n::apply_language(language);
+    let theme = theme::load_theme().unwrap_or_default();
+    theme::apply_theme(theme);
+
+    let mut model = Model {
+        history: history::load_history(),
+        scenario_library: ScenarioLibrary {
+            scenarios: scenarios::load(),
+            ..ScenarioLibrary::default()
+        },
+        theme,
+        ..Model::default()
+    };
+    let is_permalink =
+        query_string::get(&url, "banner").is_some() || query_string::get(&url, "goal").is_some();
+    if !is_permalink {
+        if let Some((current_run, session)) = history::load_session() {
+            model.banner = session.banner;
+            model.goal = session.goal;
+            model.data = session.data;
+            model.precision.achieved = session.precision;
+            model.current_run = current_run;
+        }
+    }
+    AfterMount::new(model)
+}
+
 #[wasm_bindgen]
 pub fn render() {
     seed::App::builder(update, view)
+        .after_mount(after_mount)
         .routes(routes)
+        .window_events(window_events)
         .build_and_start();
 }
@@ -0,0 +1,84 @@
+//! Running large numbers of simulations across several threads. Only
+//! available in native builds, for long runs outside of the browser.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use crate::{Banner, Goal, Histogram, Sim};
+
+/// Number of trials in each chunk of work. Each chunk is run by its own `Sim`
+/// with a seed based on the chunk's index, so the results only depend on the
+/// seed, and not on the number of threads or which thread runs which chunk.
+const CHUNK_SIZE: u64 = 1 << 14;
+
+/// Number of threads that the machine can run at once.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Runs `trials` simulations of reaching the goal on the banner, split across
+/// `threads` threads, and counts how many took each number of orbs. The same
+/// seed always gives the same results.
+pub fn run(banner: Banner, goal: &Goal, trials: u64, seed: u64, threads: usize) -> Histogram {
+    let chunks = trials.div_ceil(CHUNK_SIZE);
+    let next_chunk = AtomicU64::new(0);
+    let run_chunks = || {
+        let mut data = Histogram::default();
+        loop {
+            let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+            if chunk >= chunks {
+                return data;
+            }
+            let mut sim = Sim::with_seed(banner, goal.clone(), chunk_seed(seed, chunk));
+            for _ in 0..CHUNK_SIZE.min(trials - chunk * CHUNK_SIZE) {
+                data.record(sim.roll_until_goal());
+            }
+        }
+    };
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(run_chunks))
+            .collect();
+        // Adding counts gives the same total in any order.
+        let mut data = Histogram::default();
+        for worker in workers {
+            data += worker.join().unwrap();
+        }
+        data
+    })
+}
+
+/// The seed for the `Sim` that runs a chunk of trials.
+fn chunk_seed(seed: u64, chunk: u64) -> u64 {
+    // Spread out the chunk indexes so that nearby seeds don't give nearby
+    // chunk seeds.
+    seed ^ chunk.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::GoalPreset;
+
+    #[test]
+    fn runs_every_trial() {
+        let goal = Goal::Preset(GoalPreset::AnyFocus, 1);
+        let trials = 3 * CHUNK_SIZE + 5;
+        let data = run(Banner::default(), &goal, trials, 0, 3);
+        assert_eq!(data.total(), trials);
+    }
+
+    #[test]
+    fn results_do_not_depend_on_threads() {
+        let goal = Goal::Preset(GoalPreset::AnyFocus, 1);
+        let trials = 2 * CHUNK_SIZE + 100;
+        let single = run(Banner::default(), &goal, trials, 42, 1);
+        for threads in 2..=4 {
+            let multi = run(Banner::default(), &goal, trials, 42, threads);
+            assert_eq!(single, multi);
+        }
+        let other_seed = run(Banner::default(), &goal, trials, 43, 2);
+        assert_ne!(single, other_seed);
+    }
+}
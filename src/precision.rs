@@ -0,0 +1,327 @@
+use seed::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    banner::Banner,
+    batch,
+    goal::Goal,
+    histogram::Histogram,
+    i18n::{self, tr, tr_args},
+    sim::Sim,
+    stats, sweep, Msg,
+};
+
+/// Fewest samples to trust an estimate of the precision. With only a handful
+/// of samples the percentiles often land on the same value by chance, which
+/// makes them look far more precise than they are.
+const MIN_SAMPLES: u64 = 1000;
+
+/// How precise a run needs to be before it stops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrecisionSettings {
+    /// Largest acceptable standard error of each chosen percentile, as a
+    /// percentage of the percentile's value.
+    pub tolerance: f32,
+    /// The percentiles that need to be precise, from `sweep::PERCENTILE_OPTIONS`.
+    pub percentiles: Vec<u8>,
+    /// Most samples to gather, whether or not the tolerance has been reached.
+    pub max_samples: u64,
+}
+
+impl Default for PrecisionSettings {
+    fn default() -> Self {
+        PrecisionSettings {
+            tolerance: 1.0,
+            percentiles: vec![50, 90],
+            max_samples: 1_000_000,
+        }
+    }
+}
+
+/// How precise a set of results is. Saved along with the results, so that
+/// it can be shown again when they are reopened.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Precision {
+    pub samples: u64,
+    /// Largest standard error of the chosen percentiles, as a percentage of
+    /// the percentile's value.
+    pub relative_error: f64,
+    /// The tolerance that the error was measured against.
+    pub tolerance: f32,
+    /// Whether the error is within the tolerance.
+    pub met: bool,
+}
+
+impl PrecisionSettings {
+    /// Measures how precise the chosen percentiles of the data are.
+    pub fn measure(&self, data: &Histogram) -> Precision {
+        let samples = stats::count(data);
+        let relative_error = self
+            .percentiles
+            .iter()
+            .map(|&percentile| {
+                let pct = percentile as f32 / 100.0;
+                let value = stats::percentile(data, pct) as f64;
+                let error = stats::percentile_std_error(data, pct);
+                if error == 0.0 {
+                    0.0
+                } else {
+                    error / value * 100.0
+                }
+            })
+            .fold(0.0, f64::max);
+        Precision {
+            samples,
+            relative_error,
+            tolerance: self.tolerance,
+            met: samples >= MIN_SAMPLES && relative_error <= self.tolerance as f64,
+        }
+    }
+
+    /// Whether a run with the given precision should stop.
+    fn is_done(&self, precision: &Precision) -> bool {
+        precision.met || precision.samples >= self.max_samples
+    }
+
+    /// Describes why the settings can't be used to run, if they can't.
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.percentiles.is_empty() {
+            Some(tr("Choose at least one percentile."))
+        } else {
+            None
+        }
+    }
+}
+
+/// Settings and progress of running until the results are precise enough.
+#[derive(Default, Debug)]
+pub struct PrecisionState {
+    pub settings: PrecisionSettings,
+    /// The simulator for the run in progress, if there is one.
+    sim: Option<Sim>,
+    /// Precision of the results, as of the end of the last step of the run.
+    /// Kept once the run is done, until the results change some other way.
+    pub achieved: Option<Precision>,
+}
+
+impl PrecisionState {
+    pub fn is_running(&self) -> bool {
+        self.sim.is_some()
+    }
+
+    /// Starts a run that adds to the existing data.
+    pub fn start(&mut self, banner: Banner, goal: Goal, data: &Histogram) {
+        self.sim = Some(Sim::new(banner, goal));
+        self.achieved = Some(self.settings.measure(data));
+    }
+
+    pub fn stop(&mut self) {
+        self.sim = None;
+    }
+
+    /// Forgets the run and its precision, for when the results are changed by
+    /// something else.
+    pub fn reset(&mut self) {
+        self.sim = None;
+        self.achieved = None;
+    }
+
+    /// Adds samples to the data for a short time, returning whether there
+    /// are more to add.
+    pub fn step(&mut self, data: &mut Histogram) -> bool {
+        let sim = match &mut self.sim {
+            Some(sim) => sim,
+            None => return false,
+        };
+        let settings = &self.settings;
+        let mut precision = settings.measure(data);
+        batch::run_for_a_step(|chunk_size| {
+            if settings.is_done(&precision) {
+                return false;
+            }
+            for _ in 0..chunk_size.min(settings.max_samples - precision.samples) {
+                data.record(sim.roll_until_goal());
+            }
+            precision = settings.measure(data);
+            !settings.is_done(&precision)
+        });
+        self.achieved = Some(precision);
+        if self.settings.is_done(&precision) {
+            self.sim = None;
+        }
+        self.sim.is_some()
+    }
+}
+
+/// Describes the precision of the results.
+fn describe(settings: &PrecisionSettings, precision: &Precision, running: bool) -> String {
+    let error = tr_args(
+        "{count} samples, within ±{error} (target ±{target}%)",
+        &[
+            ("count", &i18n::number(precision.samples as f64, 0)),
+            ("error", &i18n::percent(precision.relative_error / 100.0, 2)),
+            ("target", &precision.tolerance),
+        ],
+    );
+    if running {
+        tr_args(
+            "Running: {progress}, {share} of the sample limit",
+            &[
+                ("progress", &error),
+                (
+                    "share",
+                    &i18n::percent(precision.samples as f64 / settings.max_samples as f64, 0),
+                ),
+            ],
+        )
+    } else if precision.met {
+        tr_args("Reached the target: {progress}", &[("progress", &error)])
+    } else {
+        tr_args(
+            "Stopped before reaching the target: {progress}",
+            &[("progress", &error)],
+        )
+    }
+}
+
+/// Inputs for running until the results are precise enough, and the
+/// progress of the run.
+pub fn precision_controls(state: &PrecisionState, can_run: bool) -> Node<Msg> {
+    let settings = &state.settings;
+    let running = state.is_running();
+    let problem = settings.problem();
+    details![
+        id!["precision"],
+        summary![tr("Run to precision")],
+        div![
+            label![attrs![At::For => "precision_tolerance"], tr("Standard error within ±")],
+            input![
+                id!["precision_tolerance"],
+                input_ev(Ev::Input, |text| match text.parse::<f32>() {
+                    Ok(tolerance) if tolerance > 0.0 => Msg::PrecisionToleranceChange { tolerance },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Value => settings.tolerance;
+                    At::Min => 0.05;
+                    At::Step => 0.05;
+                ],
+            ],
+            tr("% of the value"),
+        ],
+        div![
+            tr("Percentiles:"),
+            sweep::PERCENTILE_OPTIONS
+                .iter()
+                .map(|&percentile| {
+                    let id = format!("precision_percentile_{}", percentile);
+                    span![
+                        class!["padleft"],
+                        input![
+                            id![&id],
+                            simple_ev(Ev::Input, Msg::PrecisionPercentileToggle { percentile }),
+                            attrs![
+                                At::Type => "checkbox";
+                                At::Checked => settings.percentiles.contains(&percentile).as_at_value();
+                            ],
+                        ],
+                        label![attrs![At::For => id], format!("{}%", percentile)],
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        ],
+        div![
+            label![attrs![At::For => "precision_max_samples"], tr("Stop after at most ")],
+            input![
+                id!["precision_max_samples"],
+                input_ev(Ev::Input, |text| match text.parse::<u64>() {
+                    Ok(samples) if samples > 0 => Msg::PrecisionMaxSamplesChange { samples },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Value => settings.max_samples;
+                    At::Min => 1;
+                    At::Step => 100_000;
+                ],
+            ],
+            tr(" samples"),
+        ],
+        div![
+            if running {
+                button![simple_ev(Ev::Click, Msg::PrecisionStop), tr("Stop")]
+            } else {
+                button![
+                    simple_ev(Ev::Click, Msg::PrecisionStart),
+                    if !can_run {
+                        attrs![
+                            At::Disabled => true;
+                            At::Title => tr("The goal can't be completed on this banner");
+                        ]
+                    } else if let Some(problem) = problem {
+                        attrs![At::Disabled => true; At::Title => problem]
+                    } else {
+                        attrs![]
+                    },
+                    tr("Run until precise")
+                ]
+            },
+            match &state.achieved {
+                Some(precision) => span![
+                    class!["padleft"],
+                    describe(settings, precision, running)
+                ],
+                None => empty![],
+            },
+        ],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Data with `copies` samples of every value from 1 to 100.
+    fn uniform(copies: u32) -> Histogram {
+        let mut data = Histogram::default();
+        for value in 1..=100 {
+            data.add_count(value, copies as u64);
+        }
+        data
+    }
+
+    #[test]
+    fn more_data_is_more_precise() {
+        let settings = PrecisionSettings::default();
+        let small = settings.measure(&uniform(10));
+        let large = settings.measure(&uniform(10_000));
+        assert_eq!(small.samples, 1000);
+        assert!(large.relative_error < small.relative_error);
+        assert!(!small.met);
+        assert!(large.met);
+    }
+
+    #[test]
+    fn too_few_samples_never_meet_the_target() {
+        let settings = PrecisionSettings {
+            tolerance: 100.0,
+            ..PrecisionSettings::default()
+        };
+        assert!(!settings.measure(&uniform(1)).met);
+        assert!(settings.measure(&uniform(10)).met);
+    }
+
+    #[test]
+    fn stops_at_the_sample_limit() {
+        let settings = PrecisionSettings {
+            tolerance: 0.0001,
+            max_samples: 1000,
+            ..PrecisionSettings::default()
+        };
+        let precision = settings.measure(&uniform(10));
+        assert!(!precision.met);
+        assert!(settings.is_done(&precision));
+    }
+}
@@ -10,3 +10,30 @@ pub fn get<'a>(url: &'a seed::Url, param: &str) -> Option<&'a str> {
         None
     })
 }
+
+/// Sets a parameter in a query string, without the leading `?`, replacing any
+/// value it already has.
+pub fn set(search: &str, param: &str, value: &str) -> String {
+    let new_part = format!("{}={}", param, value);
+    search
+        .split('&')
+        .filter(|part| !part.is_empty() && part.split('=').next() != Some(param))
+        .chain(std::iter::once(new_part.as_str()))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_replaces_or_appends() {
+        assert_eq!(set("", "lang", "ja"), "lang=ja");
+        assert_eq!(set("v=3&lang=en&run=1", "lang", "ja"), "v=3&run=1&lang=ja");
+        assert_eq!(
+            set("v=3&language=en", "lang", "ja"),
+            "v=3&language=en&lang=ja"
+        );
+    }
+}
@@ -1,13 +1,90 @@
 use seed::prelude::*;
 
-use crate::counter::Counter;
+use std::fmt;
+
+use strum_macros::EnumIter;
+
+use crate::histogram::Histogram;
+use crate::i18n::tr;
 use crate::Msg;
 
+mod summary_table;
 mod svg_graph;
 
-/// Section for displaying the results. If `highlight` is given, places a label
-/// on the graph at the specified point. Otherwise, labels are placed at pre-set
-/// locations.
-pub fn results(data: &Counter, highlight: Option<f32>) -> Node<Msg> {
-    div![id!["results"], svg_graph::graph(data, highlight),]
+/// The different ways of displaying the results on the graph.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, EnumIter)]
+pub enum GraphView {
+    /// Orbs spent for each percentile of results.
+    #[default]
+    Percentile,
+    /// How often each amount of orbs spent happened.
+    Histogram,
+    /// Chance of having reached the goal after each amount of orbs spent.
+    Cumulative,
+    /// Chance of not yet having reached the goal after each amount of orbs spent.
+    Survival,
+}
+
+impl fmt::Display for GraphView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use GraphView::*;
+        let s = match *self {
+            Percentile => "Percentiles",
+            Histogram => "Histogram",
+            Cumulative => "Chance of success",
+            Survival => "Chance of failure",
+        };
+        f.write_str(tr(s))
+    }
+}
+
+/// Display settings for the graph, along with the state of the user's
+/// interaction with it. Positions on the graph are given in the units of the
+/// horizontal axis of the current view.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GraphState {
+    /// The way the results are displayed.
+    pub view: GraphView,
+    /// Whether the axis showing orbs spent uses a logarithmic scale.
+    pub log_scale: bool,
+    /// The range of the horizontal axis that is zoomed in on, if any.
+    pub zoom: Option<(f32, f32)>,
+    /// The point that the user has chosen to highlight.
+    pub highlight: Option<f32>,
+    /// The point that the cursor is over, if any.
+    pub hover: Option<f32>,
+    /// The point under the finger that is scrubbing along the graph on a
+    /// touch screen, if any.
+    pub scrub: Option<f32>,
+    /// Where the cursor was pressed down, if a drag might be in progress.
+    pub drag_start: Option<f32>,
+}
+
+/// Section for displaying the results using the settings in `graph`.
+/// `threshold` is the orb count used for the summary table's
+/// chance-of-exceeding row.
+pub fn results(data: &Histogram, graph: &GraphState, threshold: Option<u32>) -> Node<Msg> {
+    div![
+        id!["results"],
+        div![
+            id!["graph_container"],
+            svg_graph::graph_controls(graph),
+            svg_graph::graph(data, graph),
+            svg_graph::percentile_table(data),
+        ],
+        summary_table::summary_table(data, threshold),
+        if data.is_empty() {
+            seed::empty()
+        } else {
+            div![
+                id!["graph_export"],
+                button![simple_ev(Ev::Click, Msg::ExportSvg), tr("Download SVG")],
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::ExportPng),
+                    tr("Download PNG")
+                ],
+            ]
+        },
+    ]
 }
@@ -0,0 +1,61 @@
+use seed::prelude::*;
+
+use crate::histogram::Histogram;
+use crate::i18n::{self, tr, tr_args};
+use crate::stats;
+use crate::Msg;
+
+/// Table of summary statistics for the results. If `threshold` is given, also
+/// includes the chance of spending more than that many orbs.
+pub fn summary_table(data: &Histogram, threshold: Option<u32>) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+
+    let row = |name: &'static str, value: String| -> Node<Msg> { tr![th![tr(name)], td![value]] };
+    let orbs = |value: f64, decimals: usize| {
+        tr_args("{orbs} orbs", &[("orbs", &i18n::number(value, decimals))])
+    };
+
+    table![
+        id!["summary"],
+        tbody![
+            row("Mean", orbs(stats::mean(data), 1)),
+            row("Median", orbs(stats::percentile(data, 0.5) as f64, 0)),
+            row("Mode", orbs(stats::mode(data) as f64, 0)),
+            row("Std. deviation", orbs(stats::std_dev(data), 1)),
+            row(
+                "Worst 10% average",
+                orbs(stats::expected_shortfall(data, 0.1), 1)
+            ),
+            tr![
+                th![
+                    label![
+                        attrs![
+                            At::For => "summary_threshold";
+                        ],
+                        tr("Chance of more than "),
+                    ],
+                    input![
+                        id!["summary_threshold"],
+                        input_ev(Ev::Input, |text| Msg::SummaryThresholdChange {
+                            orbs: text.parse::<u32>().ok(),
+                        }),
+                        attrs![
+                            At::Type => "number";
+                            At::Class => "small_number";
+                            At::Min => 0;
+                            At::Value => threshold.map(|x| x.to_string()).unwrap_or_default();
+                        ],
+                    ],
+                    tr(" orbs"),
+                ],
+                td![if let Some(threshold) = threshold {
+                    i18n::percent(stats::prob_exceeding(data, threshold) as f64, 1)
+                } else {
+                    "-".to_string()
+                }],
+            ],
+        ],
+    ]
+}
@@ -2,162 +2,893 @@ use seed::prelude::*;
 
 use std::fmt::Write;
 
+use strum::IntoEnumIterator;
+
 use wasm_bindgen::JsCast;
 
-use crate::counter::Counter;
+use crate::histogram::Histogram;
+use crate::i18n::{self, tr, tr_args};
+use crate::results::{GraphState, GraphView};
 use crate::stats;
 use crate::Msg;
+
 const XMIN: f32 = 0.0;
 const YMIN: f32 = 0.0;
 const WIDTH: f32 = 100.0;
 const HEIGHT: f32 = 60.0;
 
-/// SVG elements for displaying the results within the graph. If `highlight` is
-/// given, places a label on the graph at the specified point. Otherwise, labels
-/// are placed at pre-set locations. Returns two elements, one for the line and
-/// one for the collection of labels.
-fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>) {
-    // Sample every 0.1% in ranges 0%-10% and 90%-100%, and every 1% in between.
-    // Probabilities only change sharply near the extremes, so this makes things
-    // render more quickly without hurting smoothness.
-    let sample_points = (0..100)
-        .map(|x| x as f32 / 1000.0)
-        .chain((10..90).map(|x| x as f32 / 100.0))
-        .chain((900..1000).map(|x| x as f32 / 1000.0))
-        .collect::<Vec<_>>();
-    let data_points = stats::percentiles(data, &sample_points);
+// Extra room around the plotting area for the axis labels.
+const LEFT_MARGIN: f32 = 10.0;
+const RIGHT_MARGIN: f32 = 3.0;
+const TOP_MARGIN: f32 = 2.0;
+const BOTTOM_MARGIN: f32 = 8.0;
+
+/// Rough number of labelled ticks to put on each axis.
+const TARGET_TICKS: f32 = 6.0;
+
+/// Percentiles at which the labels are placed when nothing is highlighted.
+const DEFAULT_LABEL_PCTS: [f32; 5] = [0.25, 0.5, 0.75, 0.9, 0.99];
+
+/// Number of bins that the histogram view aims to use.
+const HISTOGRAM_BINS: f32 = 40.0;
+
+/// Smallest part of the visible horizontal axis that a drag needs to cover to
+/// count as zooming instead of clicking.
+const MIN_DRAG_FRAC: f32 = 0.02;
+
+/// Mapping between data values and positions along one of the graph's axes.
+#[derive(Copy, Clone, Debug)]
+struct Scale {
+    min: f32,
+    max: f32,
+    log: bool,
+}
+
+impl Scale {
+    /// Creates a scale covering `min` to `max`. Logarithmic scales can't reach
+    /// zero, so they start at 1 at the lowest.
+    fn new(min: f32, max: f32, log: bool) -> Self {
+        let min = if log { min.max(1.0) } else { min };
+        let max = if max > min { max } else { min + 1.0 };
+        Scale { min, max, log }
+    }
+
+    /// How far along the axis the value is, where 0 and 1 are the ends of the
+    /// visible range.
+    fn frac(&self, value: f32) -> f32 {
+        if self.log {
+            (value.max(self.min).ln() - self.min.ln()) / (self.max.ln() - self.min.ln())
+        } else {
+            (value - self.min) / (self.max - self.min)
+        }
+    }
+
+    /// The value at a given fraction of the way along the axis. Inverse of `frac`.
+    fn value(&self, frac: f32) -> f32 {
+        if self.log {
+            (self.min.ln() + frac * (self.max.ln() - self.min.ln())).exp()
+        } else {
+            self.min + frac * (self.max - self.min)
+        }
+    }
 
-    // Helper functions for converting between data values and graph coordinates.
-    let x = |pct: f32| pct as f32 * WIDTH + XMIN;
-    let y = |val: f32| {
-        let max = *data_points.last().unwrap() as f32;
-        HEIGHT - (val / max) * HEIGHT
+    /// Round values within the scale's range to put tick marks at, with the
+    /// spacing between them for linear scales.
+    fn ticks(&self) -> (Vec<f32>, f32) {
+        if self.log {
+            // 1, 2, 5, 10, 20, 50...
+            let mut values = vec![];
+            let mut magnitude = 10f32.powf(self.min.log10().floor());
+            while magnitude <= self.max {
+                for &step in &[1.0, 2.0, 5.0] {
+                    let value = step * magnitude;
+                    if value >= self.min && value <= self.max {
+                        values.push(value);
+                    }
+                }
+                magnitude *= 10.0;
+            }
+            if values.len() >= 2 {
+                return (values, 1.0);
+            }
+        }
+        let spacing = tick_spacing(self.max - self.min);
+        let first = (self.min / spacing).ceil() as i32;
+        let values = (first..)
+            .map(|i| i as f32 * spacing)
+            .take_while(|&value| value <= self.max + spacing * 0.0001)
+            .collect();
+        (values, spacing)
+    }
+}
+
+/// Picks a round spacing between ticks so that an axis covering `range` gets
+/// roughly `TARGET_TICKS` ticks.
+fn tick_spacing(range: f32) -> f32 {
+    let raw = (range / TARGET_TICKS).max(f32::EPSILON);
+    let magnitude = 10f32.powf(raw.log10().floor());
+    let residual = raw / magnitude;
+    let nice = if residual < 1.5 {
+        1.0
+    } else if residual < 3.5 {
+        2.0
+    } else if residual < 7.5 {
+        5.0
+    } else {
+        10.0
     };
+    nice * magnitude
+}
 
-    let mut path = String::new();
-    if !data.is_empty() {
-        write!(
-            path,
-            "M {} {} ",
-            x(sample_points[0]),
-            y(data_points[0] as f32)
+/// Formats a tick value, showing decimals only when the spacing needs them.
+fn tick_label(value: f32, spacing: f32) -> String {
+    if spacing >= 1.0 {
+        format!("{}", value.round())
+    } else if spacing >= 0.1 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// The scales that a graph view uses for each axis. `x` is shown as
+/// percentages if `x_is_pct` is set, as is `y` with `y_is_pct`.
+struct Scales {
+    x: Scale,
+    y: Scale,
+    x_is_pct: bool,
+    y_is_pct: bool,
+}
+
+impl Scales {
+    /// Horizontal graph coordinate of a value.
+    fn cx(&self, value: f32) -> f32 {
+        XMIN + self.x.frac(value) * WIDTH
+    }
+
+    /// Vertical graph coordinate of a value.
+    fn cy(&self, value: f32) -> f32 {
+        YMIN + HEIGHT - self.y.frac(value) * HEIGHT
+    }
+}
+
+/// The largest orb value shown on the views that put orbs on the horizontal
+/// axis. Matches the top of the percentile view so the views are comparable.
+fn orb_axis_max(data: &Histogram) -> u32 {
+    stats::percentile(data, 0.999).max(1)
+}
+
+/// The scale for the orbs axis of the views that put orbs on the horizontal
+/// axis, taking the zoom level into account.
+fn orb_axis(data: &Histogram, state: &GraphState) -> Scale {
+    let (min, max) = state.zoom.unwrap_or_else(|| {
+        let min = if state.log_scale {
+            stats::percentile(data, 0.0)
+        } else {
+            0
+        };
+        (min as f32, orb_axis_max(data) as f32)
+    });
+    Scale::new(min, max, state.log_scale)
+}
+
+/// Percentiles to sample for drawing the line between `lo` and `hi`. Samples
+/// ten times as often within 10% of either end of the distribution, since
+/// probabilities only change sharply near the extremes. This makes things
+/// render more quickly without hurting smoothness.
+fn sample_points(lo: f32, hi: f32) -> Vec<f32> {
+    let fine = (hi - lo) / 1000.0;
+    let coarse = (hi - lo) / 100.0;
+    let mut points = vec![];
+    let mut pct = lo;
+    while pct < hi {
+        points.push(pct);
+        pct += if (0.1..0.9).contains(&pct) {
+            coarse
+        } else {
+            fine
+        };
+    }
+    points
+}
+
+/// Positioning attributes for a label attached to the point (`cx`, `cy`) on a
+/// line that is `rising` or falling from left to right. Labels go above the
+/// line on the side it is coming from, or below it on the other side if there
+/// isn't room, or snap to the edge of the graph if there's room for neither.
+fn label_attrs(cx: f32, cy: f32, rising: bool) -> seed::Attrs {
+    let (room_before, anchor, other_anchor, dx, edge) = if rising {
+        (cx > XMIN + WIDTH * 0.24, "end", "begin", -1.0, XMIN + 1.0)
+    } else {
+        (
+            cx < XMIN + WIDTH * 0.76,
+            "begin",
+            "end",
+            1.0,
+            XMIN + WIDTH - 1.0,
         )
-        .unwrap();
-        for i in 1..data_points.len() {
-            if data_points[i] != data_points[i - 1] {
-                write!(
-                    path,
-                    "L {} {}",
-                    x(sample_points[i]),
-                    y(data_points[i] as f32)
-                )
-                .unwrap();
-            }
+    };
+    if room_before {
+        attrs![
+            "dx" => cx + dx;
+            "dy" => cy - 1.0;
+            "text-anchor" => anchor;
+            "dominant-baseline" => "baseline";
+        ]
+    } else if cy < YMIN + HEIGHT * 0.9 {
+        attrs![
+            "dx" => cx - dx;
+            "dy" => cy + 1.0;
+            "text-anchor" => other_anchor;
+            "dominant-baseline" => "hanging";
+        ]
+    } else {
+        attrs![
+            "dx" => edge;
+            "dy" => cy - 1.0;
+            "text-anchor" => other_anchor;
+            "dominant-baseline" => "baseline";
+        ]
+    }
+}
+
+/// A highlighted point on the line with its label.
+fn point_label(cx: f32, cy: f32, rising: bool, label_text: String) -> Vec<Node<Msg>> {
+    vec![
+        circle![attrs![
+            "cx" => cx;
+            "cy" => cy;
+            "r" => "0.75px";
+        ]],
+        text![
+            attrs![
+                "font-size" => "15%";
+            ],
+            label_attrs(cx, cy, rising),
+            label_text,
+        ],
+    ]
+}
+
+/// SVG path through the given points, skipping points that would only
+/// continue a horizontal run.
+fn line_path(points: impl Iterator<Item = (f32, f32)>) -> Node<Msg> {
+    let mut path = String::new();
+    let mut last_y = None;
+    for (cx, cy) in points {
+        if last_y.is_none() {
+            write!(path, "M {} {} ", cx, cy).unwrap();
+        } else if last_y != Some(cy) {
+            write!(path, "L {} {}", cx, cy).unwrap();
         }
+        last_y = Some(cy);
     }
-    let path_el = path![
+    path![
         id!["graph_line"],
         attrs![
             "d" => path;
         ],
-    ];
-    let mut points_el = g![id!["graph_highlights"],];
+    ]
+}
+
+/// The elements of the graph that depend on which view is shown.
+struct ViewEls {
+    scales: Scales,
+    line: Node<Msg>,
+    points: Node<Msg>,
+    /// Text of each label in `points`, for screen readers.
+    labels: Vec<String>,
+    x_title: &'static str,
+    y_title: &'static str,
+}
+
+/// SVG elements for displaying the orbs spent for each percentile of results.
+/// If `label_at` is given, places a label on the graph at that percentile.
+/// Otherwise, labels are placed at pre-set locations.
+fn percentile_line(data: &Histogram, state: &GraphState, label_at: Option<f32>) -> ViewEls {
+    let (lo, hi) = state.zoom.unwrap_or((0.0, 1.0));
+    let sample_points = sample_points(lo, hi);
+    let data_points = stats::percentiles(data, &sample_points);
+
+    let min = if state.log_scale { data_points[0] } else { 0 };
+    let scales = Scales {
+        x: Scale::new(lo, hi, false),
+        y: Scale::new(
+            min as f32,
+            *data_points.last().unwrap() as f32,
+            state.log_scale,
+        ),
+        x_is_pct: true,
+        y_is_pct: false,
+    };
+
+    let line = line_path(
+        sample_points
+            .iter()
+            .zip(&data_points)
+            .map(|(&pct, &value)| (scales.cx(pct), scales.cy(value as f32))),
+    );
+
+    let mut points = g![id!["graph_highlights"],];
+    let mut labels = vec![];
     let mut add_point = |pct: f32| {
         let value = stats::percentile(data, pct) as f32;
-        points_el.add_child(circle![attrs![
-            "cx" => x(pct);
-            "cy" => y(value);
-            "r" => "0.75px";
-        ]]);
-        let label_text = format!("{}%: {} orbs", (pct * 1000.0).round() / 10.0, value);
-        points_el.add_child(text![
+        let label_text = tr_args(
+            "{pct}%: {orbs} orbs",
+            &[("pct", &((pct * 1000.0).round() / 10.0)), ("orbs", &value)],
+        );
+        labels.push(label_text.clone());
+        for node in point_label(scales.cx(pct), scales.cy(value), true, label_text) {
+            points.add_child(node);
+        }
+    };
+    if let Some(pct) = label_at {
+        add_point(pct);
+    } else {
+        for &pct in DEFAULT_LABEL_PCTS
+            .iter()
+            .filter(|&&pct| pct >= lo && pct < hi)
+        {
+            add_point(pct);
+        }
+    }
+
+    ViewEls {
+        scales,
+        line,
+        points,
+        labels,
+        x_title: tr("Percentile"),
+        y_title: tr("Orbs spent"),
+    }
+}
+
+/// SVG elements for displaying the cumulative distribution of the results, with
+/// orbs spent on the horizontal axis. If `survival` is set, shows the chance of
+/// not having reached the goal yet instead of the chance of having reached it.
+/// If `label_at` is given, places a label at that number of orbs. Otherwise,
+/// labels are placed at pre-set percentiles.
+fn cumulative_line(
+    data: &Histogram,
+    state: &GraphState,
+    label_at: Option<f32>,
+    survival: bool,
+) -> ViewEls {
+    let total = stats::count(data) as f32;
+    let scales = Scales {
+        x: orb_axis(data, state),
+        y: Scale::new(0.0, 1.0, false),
+        x_is_pct: false,
+        y_is_pct: true,
+    };
+    let (lo, hi) = (scales.x.min as u32, scales.x.max as u32);
+
+    let shown = |frac: f32| if survival { 1.0 - frac } else { frac };
+    let cumulative = |orbs: u32| data.count_at_most(orbs) as f32 / total;
+
+    let mut accum = cumulative(lo) * total;
+    let line = line_path(
+        std::iter::once((scales.cx(lo as f32), scales.cy(shown(accum / total)))).chain(
+            data.iter()
+                .filter(|&(orbs, _)| orbs > lo && orbs <= hi)
+                .map(|(orbs, count)| {
+                    accum += count as f32;
+                    (scales.cx(orbs as f32), scales.cy(shown(accum / total)))
+                })
+                .collect::<Vec<_>>(),
+        ),
+    );
+
+    let mut points = g![id!["graph_highlights"],];
+    let mut labels = vec![];
+    let mut add_point = |orbs: u32| {
+        let frac = shown(cumulative(orbs));
+        let label_text = if survival {
+            tr_args(
+                "{orbs} orbs: {pct}% not done",
+                &[("orbs", &orbs), ("pct", &((frac * 1000.0).round() / 10.0))],
+            )
+        } else {
+            tr_args(
+                "{orbs} orbs: {pct}% done",
+                &[("orbs", &orbs), ("pct", &((frac * 1000.0).round() / 10.0))],
+            )
+        };
+        let cx = scales.cx(orbs as f32);
+        labels.push(label_text.clone());
+        for node in point_label(cx, scales.cy(frac), !survival, label_text) {
+            points.add_child(node);
+        }
+    };
+    if let Some(orbs) = label_at {
+        add_point(orbs.round() as u32);
+    } else {
+        for &orbs in &stats::percentiles(data, &DEFAULT_LABEL_PCTS) {
+            if orbs >= lo && orbs <= hi {
+                add_point(orbs);
+            }
+        }
+    }
+
+    ViewEls {
+        scales,
+        line,
+        points,
+        labels,
+        x_title: tr("Orbs spent"),
+        y_title: tr(if survival {
+            "Chance of failure"
+        } else {
+            "Chance of success"
+        }),
+    }
+}
+
+/// SVG elements for displaying a histogram of the results. If `label_at` is
+/// given, labels the bin containing that number of orbs. Otherwise, labels the
+/// tallest bin.
+fn histogram_bars(data: &Histogram, state: &GraphState, label_at: Option<f32>) -> ViewEls {
+    let total = stats::count(data) as f32;
+    let x = orb_axis(data, state);
+    let (lo, hi) = (x.min as u32, x.max as u32);
+
+    // Orb costs come in steps of 4 or 5 per session, so narrower bins would
+    // just show gaps between the possible costs.
+    let bin_width = ((hi - lo + 1) as f32 / HISTOGRAM_BINS).ceil().max(5.0) as u32;
+    let num_bins = (hi - lo + bin_width) / bin_width;
+    let bin_start = |bin: u32| lo + bin * bin_width;
+    let bins = (0..num_bins)
+        .map(|bin| data.count_between(bin_start(bin), bin_start(bin + 1)))
+        .collect::<Vec<_>>();
+    let tallest = bins.iter().copied().max().unwrap_or(0).max(1);
+
+    let scales = Scales {
+        x,
+        y: Scale::new(0.0, tallest as f32 / total, false),
+        x_is_pct: false,
+        y_is_pct: true,
+    };
+    let bar = |bin: u32| {
+        let left = scales.cx(bin_start(bin) as f32);
+        let right = scales.cx(bin_start(bin + 1).min(hi + 1) as f32);
+        let top = scales.cy(bins[bin as usize] as f32 / total);
+        rect![attrs![
+            "x" => left;
+            "y" => top;
+            At::Width => right - left;
+            At::Height => YMIN + HEIGHT - top;
+        ]]
+    };
+
+    let mut line = g![id!["graph_bars"],];
+    for bin in 0..num_bins {
+        line.add_child(bar(bin));
+    }
+
+    let bin = if let Some(orbs) = label_at {
+        ((orbs.round() as u32).max(lo) - lo) / bin_width
+    } else {
+        bins.iter()
+            .enumerate()
+            .max_by_key(|&(bin, &count)| (count, std::cmp::Reverse(bin)))
+            .map(|(bin, _)| bin as u32)
+            .unwrap_or(0)
+    }
+    .min(num_bins - 1);
+    let share = bins[bin as usize] as f32 / total;
+    let label_text = tr_args(
+        "{lo}-{hi} orbs: {pct}%",
+        &[
+            ("lo", &bin_start(bin)),
+            ("hi", &(bin_start(bin + 1) - 1)),
+            ("pct", &((share * 1000.0).round() / 10.0)),
+        ],
+    );
+    let center = (scales.cx(bin_start(bin) as f32) + scales.cx(bin_start(bin + 1) as f32)) / 2.0;
+    let points = g![
+        id!["graph_highlights"],
+        bar(bin),
+        text![
             attrs![
                 "font-size" => "15%";
             ],
-            // By default, put the label up and to the left of the point.
-            if pct > 0.24 {
-                attrs![
-                    "dx" => x(pct) - 1.0;
-                    "dy" => y(value) - 1.0;
-                    "text-anchor" => "end";
-                    "dominant-baseline" => "baseline";
-                ]
+            label_attrs(center, scales.cy(share), bin * 2 > num_bins),
+            &label_text,
+        ],
+    ];
+
+    ViewEls {
+        scales,
+        line,
+        points,
+        labels: vec![label_text],
+        x_title: tr("Orbs spent"),
+        y_title: tr("Share of results"),
+    }
+}
+
+/// Axis lines, gridlines, tick labels, and axis titles for the graph.
+fn axes(scales: &Scales, x_title: &str, y_title: &str) -> Node<Msg> {
+    let label = |value: f32, spacing: f32, is_pct: bool| {
+        if is_pct {
+            format!("{}%", tick_label(value * 100.0, spacing * 100.0))
+        } else {
+            tick_label(value, spacing)
+        }
+    };
+
+    let mut grid = g![id!["graph_grid"],];
+    let mut labels = g![
+        id!["graph_tick_labels"],
+        attrs![
+            "font-size" => "10%";
+        ],
+    ];
+    let (x_ticks, x_spacing) = scales.x.ticks();
+    for value in x_ticks {
+        let x = scales.cx(value);
+        grid.add_child(line_![attrs![
+            "x1" => x;
+            "x2" => x;
+            "y1" => YMIN;
+            "y2" => YMIN + HEIGHT;
+        ]]);
+        labels.add_child(text![
+            attrs![
+                "x" => x;
+                "y" => YMIN + HEIGHT + 1.0;
+                "text-anchor" => "middle";
+                "dominant-baseline" => "hanging";
+            ],
+            label(value, x_spacing, scales.x_is_pct),
+        ]);
+    }
+    let (y_ticks, y_spacing) = scales.y.ticks();
+    for value in y_ticks {
+        let y = scales.cy(value);
+        grid.add_child(line_![attrs![
+            "x1" => XMIN;
+            "x2" => XMIN + WIDTH;
+            "y1" => y;
+            "y2" => y;
+        ]]);
+        labels.add_child(text![
+            attrs![
+                "x" => XMIN - 1.0;
+                "y" => y;
+                "text-anchor" => "end";
+                "dominant-baseline" => "middle";
+            ],
+            label(value, y_spacing, scales.y_is_pct),
+        ]);
+    }
+    labels.add_child(text![
+        attrs![
+            "x" => XMIN + WIDTH / 2.0;
+            "y" => YMIN + HEIGHT + BOTTOM_MARGIN - 0.5;
+            "text-anchor" => "middle";
+        ],
+        x_title,
+    ]);
+    labels.add_child(text![
+        attrs![
+            "transform" => format!(
+                "translate({} {}) rotate(-90)",
+                XMIN - LEFT_MARGIN + 0.5,
+                YMIN + HEIGHT / 2.0
+            );
+            "text-anchor" => "middle";
+            "dominant-baseline" => "hanging";
+        ],
+        y_title,
+    ]);
+    g![
+        id!["graph_axes"],
+        grid,
+        path![
+            id!["graph_axis_lines"],
+            attrs![
+                "d" => format!(
+                    "M {} {} V {} H {}",
+                    XMIN,
+                    YMIN,
+                    YMIN + HEIGHT,
+                    XMIN + WIDTH
+                );
+            ],
+        ],
+        labels,
+    ]
+}
+
+/// Controls for choosing which view of the results the graph shows and how it
+/// is scaled.
+pub fn graph_controls(state: &GraphState) -> Node<Msg> {
+    let mut tabs = div![id!["graph_tabs"],];
+    for view in GraphView::iter() {
+        tabs.add_child(button![
+            simple_ev(Ev::Click, Msg::GraphViewChange { view }),
+            if view == state.view {
+                class!["selected"]
             } else {
-                // If the point is too far to the left for the label to fit,
-                // then put it down and to the right if there is room there.
-                if y(value) < HEIGHT * 0.9 {
-                    attrs![
-                        "dx" => x(pct) + 1.0;
-                        "dy" => y(value) + 1.0;
-                        "text-anchor" => "begin";
-                        "dominant-baseline" => "hanging";
-                    ]
-                } else {
-                    // If there isn't room in either of those places, then just
-                    // snap it to the left edge, high enough to not intersect
-                    // the graph line.
-                    attrs![
-                        "dx" => 1.0;
-                        "dy" => y(stats::percentile(data, 0.24) as f32) - 1.0;
-                        "text-anchor" => "begin";
-                        "dominant-baseline" => "baseline";
-                    ]
-                }
+                class![]
             },
-            label_text,
+            view.to_string(),
         ]);
-    };
-    if !data.is_empty() {
-        if let Some(highlight) = highlight {
-            add_point(highlight);
-        } else {
-            for &pct in &[0.25, 0.5, 0.75, 0.9, 0.99] {
-                add_point(pct);
-            }
-        }
     }
-    (path_el, points_el)
+    tabs.add_child(input![
+        id!["graph_log_scale"],
+        simple_ev(Ev::Input, Msg::GraphLogScaleToggle),
+        attrs![At::Type => "checkbox"; At::Checked => state.log_scale.as_at_value()],
+    ]);
+    tabs.add_child(label![
+        attrs![At::For => "graph_log_scale"],
+        tr("Log scale")
+    ]);
+    if state.zoom.is_some() {
+        tabs.add_child(button![
+            class!["padleft"],
+            simple_ev(Ev::Click, Msg::GraphZoom { range: None }),
+            tr("Reset zoom"),
+        ]);
+    }
+    tabs
 }
 
-/// Graph for displaying the results. If `highlight` is given, places a label
-/// on the graph at the specified point. Otherwise, labels are placed at pre-set
-/// locations.
-pub fn graph(data: &Counter, highlight: Option<f32>) -> Node<Msg> {
-    let (path_el, points_el) = graph_line(data, highlight);
-    fn get_graph_width(event: &web_sys::Event) -> Option<f64> {
-        let target = event.target()?;
-        let target_el: &web_sys::Element = target.dyn_ref::<web_sys::SvgsvgElement>()?.as_ref();
-        let width = target_el.get_bounding_client_rect().width();
-        Some(width)
+/// Converts the mouse position of an event on the graph into a fraction of the
+/// way across the plotting area.
+fn event_frac(event: &web_sys::MouseEvent) -> Option<f32> {
+    let target = event.current_target()?;
+    let graph_el = target.dyn_ref::<web_sys::Element>()?;
+    let rect = graph_el.get_bounding_client_rect();
+    if rect.width() <= 0.0 {
+        return None;
     }
-    svg![
+    let view_width = LEFT_MARGIN + WIDTH + RIGHT_MARGIN;
+    let view_x = (event.client_x() as f64 - rect.left()) / rect.width() * view_width as f64;
+    let frac = (view_x as f32 - LEFT_MARGIN) / WIDTH;
+    Some(frac.min(0.999).max(0.0))
+}
+
+/// Graph for displaying the results using the view and scale in `state`. If the
+/// cursor is over the graph, places a label on the graph at the point under it.
+/// Otherwise, if a point has been highlighted, places a label there. Otherwise,
+/// labels are placed at pre-set locations. Dragging across the graph zooms in
+/// on the selected range, and the arrow keys move the highlighted point when
+/// the graph has focus. On a touch screen, dragging a finger along the graph
+/// moves the label with it, shown enlarged above the graph so that the finger
+/// doesn't hide it, and lifting the finger highlights the point. Followed by
+/// a region that reads out the highlighted point to screen readers.
+pub fn graph(data: &Histogram, state: &GraphState) -> Vec<Node<Msg>> {
+    if data.is_empty() {
+        return vec![svg![
+            id!["graph"],
+            attrs![
+                "role" => "img";
+                "aria-label" => tr("Graph of the results, empty until the simulation is run");
+                At::ViewBox => format!(
+                    "{} {} {} {}",
+                    XMIN - LEFT_MARGIN,
+                    YMIN - TOP_MARGIN,
+                    LEFT_MARGIN + WIDTH + RIGHT_MARGIN,
+                    TOP_MARGIN + HEIGHT + BOTTOM_MARGIN
+                );
+            ],
+        ]];
+    }
+
+    let pointer = state.scrub.or(state.hover);
+    let label_at = pointer.or(state.highlight);
+    let ViewEls {
+        scales,
+        line,
+        points,
+        labels,
+        x_title,
+        y_title,
+    } = match state.view {
+        GraphView::Percentile => percentile_line(data, state, label_at),
+        GraphView::Histogram => histogram_bars(data, state, label_at),
+        GraphView::Cumulative => cumulative_line(data, state, label_at, false),
+        GraphView::Survival => cumulative_line(data, state, label_at, true),
+    };
+
+    // Positions are tracked in the units of the horizontal axis so that they
+    // stay put when the zoom or scale changes.
+    let x_scale = scales.x;
+    let is_pct = scales.x_is_pct;
+    let round_value = move |value: f32| {
+        if is_pct {
+            (value * 10000.0).round() / 10000.0
+        } else {
+            value.round()
+        }
+    };
+    let event_value = move |event: &web_sys::MouseEvent| {
+        event_frac(event).map(|frac| round_value(x_scale.value(frac)))
+    };
+    let drag_start = state.drag_start;
+    let highlight = state.highlight;
+
+    // Only points chosen on purpose are read out, not every point that the
+    // cursor passes over.
+    let announcement = if pointer.is_none() && state.highlight.is_some() {
+        labels.join(", ")
+    } else {
+        String::new()
+    };
+    let readout = if state.scrub.is_some() {
+        div![
+            id!["graph_readout"],
+            attrs!["aria-hidden" => "true"],
+            labels.join(", ")
+        ]
+    } else {
+        seed::empty()
+    };
+
+    let svg = svg![
         id!["graph"],
-        mouse_ev(Ev::Click, |click| {
-            if let Some(width) = get_graph_width(&click) {
-                let width_frac = (click.offset_x() as f32 / width as f32).min(0.999).max(0.0);
-                Msg::GraphHighlight {
-                    frac: (1000.0 * width_frac).round() / 1000.0,
-                }
+        keyboard_ev(Ev::KeyDown, move |event| {
+            let (min, max) = (x_scale.min, x_scale.max);
+            let step = if is_pct {
+                0.01
+            } else {
+                ((max - min) / 100.0).round().max(1.0)
+            };
+            let step = if event.shift_key() { step * 10.0 } else { step };
+            let current = highlight.unwrap_or(min);
+            let x = match event.key().as_str() {
+                "ArrowRight" => current + step,
+                "ArrowLeft" => current - step,
+                "Home" => min,
+                "End" => max,
+                _ => return Msg::Null,
+            };
+            event.prevent_default();
+            Msg::GraphHighlight {
+                x: round_value(x.clamp(min, max)),
+            }
+        }),
+        mouse_ev(Ev::MouseDown, move |event| {
+            if let Some(x) = event_value(&event) {
+                Msg::GraphDragStart { x }
             } else {
                 Msg::Null
             }
         }),
+        mouse_ev(Ev::MouseUp, move |event| {
+            match (event_value(&event), drag_start) {
+                (Some(x), Some(start))
+                    if (x_scale.frac(x) - x_scale.frac(start)).abs() >= MIN_DRAG_FRAC =>
+                {
+                    Msg::GraphZoom {
+                        range: Some((start.min(x), start.max(x))),
+                    }
+                }
+                (Some(x), _) => Msg::GraphHighlight { x },
+                (None, _) => Msg::Null,
+            }
+        }),
+        mouse_ev(Ev::MouseMove, move |event| Msg::GraphHover {
+            x: event_value(&event),
+        }),
+        simple_ev(Ev::MouseLeave, Msg::GraphHover { x: None }),
+        // Touches are handled separately from the mouse, since a finger
+        // covers the point that it is on and can't hover.
+        pointer_ev(Ev::PointerDown, move |event| {
+            if event.pointer_type() != "touch" {
+                return Msg::Null;
+            }
+            // Stops the browser from also sending mouse events for the touch.
+            event.prevent_default();
+            Msg::GraphScrub {
+                x: event_value(&event),
+            }
+        }),
+        pointer_ev(Ev::PointerMove, move |event| {
+            if event.pointer_type() != "touch" {
+                return Msg::Null;
+            }
+            Msg::GraphScrub {
+                x: event_value(&event),
+            }
+        }),
+        pointer_ev(Ev::PointerUp, move |event| {
+            match event_value(&event) {
+                Some(x) if event.pointer_type() == "touch" => Msg::GraphHighlight { x },
+                _ => Msg::Null,
+            }
+        }),
+        // Sent when the browser takes over the touch to scroll the page.
+        simple_ev(Ev::PointerCancel, Msg::GraphScrub { x: None }),
         attrs![
-            At::ViewBox => format!("{} {} {} {}", XMIN, YMIN, WIDTH, HEIGHT);
+            At::TabIndex => 0;
+            "role" => "img";
+            "aria-label" => tr_args(
+                "Graph of {y} by {x}. Use the left and right arrow keys to move the \
+                 highlighted point, holding Shift for bigger steps.",
+                &[("y", &y_title.to_lowercase()), ("x", &x_title.to_lowercase())],
+            );
+            "aria-describedby" => "graph_percentiles";
+            At::ViewBox => format!(
+                "{} {} {} {}",
+                XMIN - LEFT_MARGIN,
+                YMIN - TOP_MARGIN,
+                LEFT_MARGIN + WIDTH + RIGHT_MARGIN,
+                TOP_MARGIN + HEIGHT + BOTTOM_MARGIN
+            );
         ],
-        path_el,
-        if !data.is_empty() {
-            text![
-                id!["graph_sample_count"],
+        axes(&scales, x_title, y_title),
+        line,
+        text![
+            id!["graph_sample_count"],
+            attrs![
+                "x" => XMIN + 1.0;
+                "y" => YMIN + 1.0;
+                "dominant-baseline" => "hanging";
+                "font-size" => "10%";
+            ],
+            tr_args(
+                "{count} samples",
+                &[("count", &i18n::number(data.total() as f64, 0))],
+            ),
+        ],
+        match (state.drag_start, pointer) {
+            (Some(start), Some(end)) => {
+                let (left, right) = (scales.cx(start.min(end)), scales.cx(start.max(end)));
+                rect![
+                    id!["graph_selection"],
+                    attrs![
+                        "x" => left;
+                        "y" => YMIN;
+                        At::Width => right - left;
+                        At::Height => HEIGHT;
+                    ],
+                ]
+            }
+            (None, Some(x)) => line_![
+                id!["graph_cursor"],
                 attrs![
-                    "dominant-baseline" => "hanging";
-                    "font-size" => "10%";
+                    "x1" => scales.cx(x);
+                    "x2" => scales.cx(x);
+                    "y1" => YMIN;
+                    "y2" => YMIN + HEIGHT;
                 ],
-                format!("{} samples", data.iter().sum::<u32>()),
-            ]
-        } else {
-            seed::empty()
+            ],
+            _ => seed::empty(),
         },
-        points_el,
+        points,
+    ];
+    vec![
+        svg,
+        readout,
+        p![
+            id!["graph_announcement"],
+            class!["visually_hidden"],
+            attrs!["aria-live" => "polite"],
+            announcement,
+        ],
+    ]
+}
+
+/// A table of the orbs spent at the percentiles that the graph labels by
+/// default, hidden from view for screen readers, which can't read the graph.
+pub fn percentile_table(data: &Histogram) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+    table![
+        id!["graph_percentiles"],
+        class!["visually_hidden"],
+        caption![tr(
+            "Orbs spent before reaching the goal, by percentile of results"
+        )],
+        thead![tr![th![tr("Percentile")], th![tr("Orbs spent")]]],
+        tbody![DEFAULT_LABEL_PCTS
+            .iter()
+            .zip(stats::percentiles(data, &DEFAULT_LABEL_PCTS))
+            .map(|(pct, orbs)| tr![th![format!("{:.0}%", pct * 100.0)], td![orbs.to_string()]])
+            .collect::<Vec<_>>()],
     ]
 }
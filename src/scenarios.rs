@@ -0,0 +1,258 @@
+use seed::prelude::*;
+
+use wasm_bindgen::JsCast;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    banner::Banner,
+    goal::Goal,
+    i18n::{tr, tr_args},
+    storage::{self, StoreError},
+    subpages, Msg,
+};
+
+/// Local storage key for the scenario library.
+const SCENARIOS_KEY: &str = "feh-sim-scenarios";
+
+/// A named banner and goal setup saved in the library.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub banner: Banner,
+    pub goal: Goal,
+}
+
+/// The saved scenarios along with the state of the scenario manager's inputs.
+#[derive(Default, Debug)]
+pub struct ScenarioLibrary {
+    pub scenarios: Vec<Scenario>,
+    /// Name to give the current setup when it is saved.
+    pub new_name: String,
+    /// Comma-separated tags to give the current setup when it is saved.
+    pub new_tags: String,
+    /// Only scenarios whose name or tags contain this text are shown.
+    pub filter: String,
+}
+
+impl Scenario {
+    /// Whether the scenario's name or one of its tags contains `filter`,
+    /// ignoring case.
+    fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        self.name.to_lowercase().contains(&filter)
+            || self
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&filter))
+    }
+}
+
+/// Splits a comma-separated list of tags.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Saves the scenario library, telling the user if it couldn't be saved.
+pub fn save(scenarios: &[Scenario]) {
+    match storage::store(SCENARIOS_KEY, &scenarios) {
+        Ok(()) | Err(StoreError::Unavailable) => {}
+        Err(_) => crate::alert(tr("The scenarios could not be saved in the browser.")),
+    }
+}
+
+/// Loads the scenario library.
+pub fn load() -> Vec<Scenario> {
+    storage::load(SCENARIOS_KEY).unwrap_or_default()
+}
+
+/// Converts scenarios to the JSON format used for importing and exporting.
+pub fn to_json(scenarios: &[Scenario]) -> String {
+    serde_json::to_string_pretty(scenarios).unwrap()
+}
+
+/// Parses scenarios from the JSON format used for importing and exporting.
+pub fn from_json(json: &str) -> Result<Vec<Scenario>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Reads the contents of a file chosen by the user as text.
+pub async fn read_file(file: web_sys::File) -> Result<String, JsValue> {
+    let reader = web_sys::FileReader::new()?;
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let onloadend = Closure::once(move || {
+        let _ = sender.send(());
+    });
+    reader.set_onloadend(Some(onloadend.as_ref().unchecked_ref()));
+    reader.read_as_text(&file)?;
+    receiver
+        .await
+        .map_err(|_| JsValue::from_str("File reading was cancelled"))?;
+    reader
+        .result()?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("File is not text"))
+}
+
+/// Row of the scenario table, with controls for editing and loading it.
+fn scenario_row(index: usize, scenario: &Scenario) -> Node<Msg> {
+    tr![
+        td![input![
+            input_ev(Ev::Change, move |name| Msg::ScenarioRename { index, name }),
+            attrs![
+                At::Type => "text";
+                At::Value => scenario.name;
+            ],
+        ]],
+        td![input![
+            input_ev(Ev::Change, move |tags| Msg::ScenarioTagsChange {
+                index,
+                tags: parse_tags(&tags),
+            }),
+            attrs![
+                At::Type => "text";
+                At::Value => scenario.tags.join(", ");
+            ],
+        ]],
+        td![format!("{} - {}", scenario.goal, scenario.banner)],
+        td![
+            button![
+                simple_ev(Ev::Click, Msg::ScenarioLoad { index }),
+                tr("Load")
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioDuplicate { index }),
+                tr("Duplicate")
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioDelete { index }),
+                tr("Delete")
+            ],
+        ],
+    ]
+}
+
+/// Page contents for the scenario manager. `banner` and `goal` are the current
+/// setup, which can be saved as a new scenario.
+pub fn scenarios_page(library: &ScenarioLibrary, banner: &Banner, goal: &Goal) -> Vec<Node<Msg>> {
+    let mut table = tbody![];
+    for (index, scenario) in library.scenarios.iter().enumerate() {
+        if scenario.matches(&library.filter) {
+            table.add_child(scenario_row(index, scenario));
+        }
+    }
+
+    vec![
+        subpages::header(),
+        h2![tr("Saved scenarios")],
+        div![
+            id!["scenario_save"],
+            p![tr_args(
+                "Current setup: {goal} - {banner}",
+                &[("goal", goal), ("banner", banner)],
+            )],
+            label![attrs![At::For => "scenario_name"], tr("Name: ")],
+            input![
+                id!["scenario_name"],
+                input_ev(Ev::Input, |name| Msg::ScenarioNewNameChange { name }),
+                attrs![
+                    At::Type => "text";
+                    At::Value => library.new_name;
+                ],
+            ],
+            label![
+                class!["padleft"],
+                attrs![At::For => "scenario_tags"],
+                tr("Tags: ")
+            ],
+            input![
+                id!["scenario_tags"],
+                input_ev(Ev::Input, |tags| Msg::ScenarioNewTagsChange { tags }),
+                attrs![
+                    At::Type => "text";
+                    At::Value => library.new_tags;
+                    At::Placeholder => tr("comma, separated");
+                ],
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioSave),
+                if library.new_name.trim().is_empty() {
+                    attrs![At::Disabled => true]
+                } else {
+                    attrs![]
+                },
+                tr("Save current setup"),
+            ],
+        ],
+        div![
+            id!["scenario_file"],
+            label![
+                attrs![At::For => "scenario_import"],
+                tr("Import from file: ")
+            ],
+            input![
+                id!["scenario_import"],
+                ev(Ev::Change, |event| {
+                    let file = event
+                        .target()
+                        .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+                        .and_then(|input| {
+                            let file = input.files().and_then(|files| files.get(0));
+                            // Clear the input so that the same file can be
+                            // chosen again.
+                            input.set_value("");
+                            file
+                        });
+                    if let Some(file) = file {
+                        Msg::ScenarioImportFile { file }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "file";
+                    At::Accept => "application/json,.json";
+                ],
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioExport),
+                tr("Export to file"),
+            ],
+        ],
+        div![
+            label![attrs![At::For => "scenario_filter"], tr("Filter: ")],
+            input![
+                id!["scenario_filter"],
+                input_ev(Ev::Input, |filter| Msg::ScenarioFilterChange { filter }),
+                attrs![
+                    At::Type => "text";
+                    At::Value => library.filter;
+                    At::Placeholder => tr("name or tag");
+                ],
+            ],
+        ],
+        if library.scenarios.is_empty() {
+            p![tr("No scenarios saved yet.")]
+        } else {
+            table![
+                id!["scenarios"],
+                thead![tr![
+                    th![tr("Name")],
+                    th![tr("Tags")],
+                    th![tr("Setup")],
+                    th![]
+                ]],
+                table,
+            ]
+        },
+    ]
+}
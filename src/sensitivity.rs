@@ -0,0 +1,380 @@
+use seed::prelude::*;
+
+use strum::IntoEnumIterator;
+
+use crate::{
+    banner::Banner,
+    batch::BatchRun,
+    goal::{CustomGoal, Goal, GoalExpr, GoalKind},
+    i18n::{tr, tr_args},
+    stats, subpages, validation, Color, Msg,
+};
+
+/// Percentiles shown for each variant, along with the mean.
+const PERCENTILES: [f32; 4] = [0.5, 0.75, 0.9, 0.99];
+
+/// The current setup with one thing changed.
+#[derive(Clone, Debug)]
+pub struct Variant {
+    /// Description of the change.
+    pub label: String,
+    pub banner: Banner,
+    pub goal: Goal,
+}
+
+/// Changes a number of copies by `delta`, keeping it at least 1. Parts that
+/// ask for no copies are left alone.
+fn change_count(count: u8, delta: i8) -> u8 {
+    if count == 0 {
+        0
+    } else {
+        (count as i16 + delta as i16).max(1).min(u8::MAX as i16) as u8
+    }
+}
+
+/// Changes the number of copies needed of every unit in the custom goal, or
+/// the total number of copies if the goal counts copies of every unit.
+fn change_custom_copies(custom: &mut CustomGoal, delta: i8) {
+    if let GoalKind::Total(count) = &mut custom.kind {
+        *count = change_count(*count, delta);
+    } else {
+        for part in &mut custom.goals {
+            part.num_copies = change_count(part.num_copies, delta);
+        }
+    }
+}
+
+/// Changes the number of copies needed in every part of a nested goal, the
+/// same way as for a custom goal.
+fn change_expr_copies(expr: &mut GoalExpr, delta: i8) {
+    match expr {
+        GoalExpr::Part { part, .. } => part.num_copies = change_count(part.num_copies, delta),
+        GoalExpr::Group {
+            kind: GoalKind::Total(count),
+            ..
+        } => *count = change_count(*count, delta),
+        GoalExpr::Group { children, .. } => {
+            for child in children {
+                change_expr_copies(child, delta);
+            }
+        }
+    }
+}
+
+/// The goal with one more or one fewer copy of each unit, if that changes it.
+fn with_copies(goal: &Goal, banner: &Banner, delta: i8) -> Option<Goal> {
+    match goal {
+        Goal::Expr(expr) => {
+            let mut changed = expr.clone();
+            change_expr_copies(&mut changed, delta);
+            if changed == *expr {
+                None
+            } else {
+                Some(Goal::Expr(changed))
+            }
+        }
+        _ => {
+            let custom = goal.as_custom(banner);
+            let mut changed = custom.clone();
+            change_custom_copies(&mut changed, delta);
+            if changed == custom {
+                None
+            } else {
+                Some(Goal::Custom(changed))
+            }
+        }
+    }
+}
+
+/// The current setup, followed by every variant of it that changes one
+/// thing by one step and can still be simulated.
+pub fn variants(banner: &Banner, goal: &Goal) -> Vec<Variant> {
+    let mut variants = vec![Variant {
+        label: tr("Current setup").into(),
+        banner: *banner,
+        goal: goal.clone(),
+    }];
+    let mut add = |label: String, banner: Banner, goal: Goal| {
+        if !validation::has_errors(&validation::validate(&goal, &banner)) {
+            variants.push(Variant {
+                label,
+                banner,
+                goal,
+            });
+        }
+    };
+
+    for &(delta, label) in &[(1, "One more copy"), (-1, "One fewer copy")] {
+        if let Some(changed) = with_copies(goal, banner, delta) {
+            add(tr(label).into(), *banner, changed);
+        }
+    }
+    for color in Color::iter() {
+        let size = banner.focus_sizes[color as usize];
+        for &(delta, label) in &[
+            (1, "One more {color} focus unit"),
+            (-1, "One fewer {color} focus unit"),
+        ] {
+            let mut changed = *banner;
+            changed.focus_sizes[color as usize] = size + delta;
+            if size >= 0 && size + delta >= 0 {
+                add(
+                    tr_args(label, &[("color", &color.to_string().to_lowercase())]),
+                    changed,
+                    goal.clone(),
+                );
+            }
+        }
+    }
+    let toggled = Banner {
+        focus_charges: !banner.focus_charges,
+        ..*banner
+    };
+    add(
+        if toggled.focus_charges {
+            tr("Focus charges on").into()
+        } else {
+            tr("Focus charges off").into()
+        },
+        toggled,
+        goal.clone(),
+    );
+
+    variants
+}
+
+/// The state of the sensitivity page.
+#[derive(Debug)]
+pub struct SensitivityState {
+    /// Number of simulations to run for each variant.
+    pub trials: u32,
+    /// Description of the setup that the current results are for.
+    pub setup: String,
+    pub variants: Vec<Variant>,
+    pub run: BatchRun,
+}
+
+impl Default for SensitivityState {
+    fn default() -> Self {
+        SensitivityState {
+            trials: 20_000,
+            setup: String::new(),
+            variants: vec![],
+            run: BatchRun::default(),
+        }
+    }
+}
+
+impl SensitivityState {
+    /// Starts running the variants of the given setup.
+    pub fn start(&mut self, banner: &Banner, goal: &Goal) {
+        self.setup = format!("{} - {}", goal, banner);
+        self.variants = variants(banner, goal);
+        let jobs = self
+            .variants
+            .iter()
+            .map(|variant| (variant.banner, variant.goal.clone()))
+            .collect();
+        self.run = BatchRun::new(jobs, self.trials);
+    }
+}
+
+/// A cell of the results table, showing how much a variant's value differs
+/// from the current setup's.
+fn difference_cell(value: f64, base: f64, precision: usize) -> Node<Msg> {
+    let difference = value - base;
+    let percent = if base > 0.0 {
+        format!(" ({:+.0}%)", difference / base * 100.0)
+    } else {
+        String::new()
+    };
+    td![
+        attrs![At::Title => format!("{:.*} orbs", precision, value)],
+        format!("{:+.*}{}", precision, difference, percent),
+    ]
+}
+
+/// The results as a table with a row for each variant. The current setup's
+/// row has its values, and the other rows have their differences from it.
+fn results_table(state: &SensitivityState) -> Node<Msg> {
+    let summaries: Vec<(f64, Vec<u32>)> = state
+        .run
+        .results
+        .iter()
+        .map(|data| (stats::mean(data), stats::percentiles(data, &PERCENTILES)))
+        .collect();
+    let (base_mean, base_percentiles) = &summaries[0];
+
+    let mut body = tbody![];
+    for (index, (variant, (mean, percentiles))) in state.variants.iter().zip(&summaries).enumerate()
+    {
+        let mut row = tr![th![&variant.label]];
+        if index == 0 {
+            row.add_child(td![format!("{:.1}", mean)]);
+            for value in percentiles {
+                row.add_child(td![value.to_string()]);
+            }
+        } else {
+            row.add_child(difference_cell(*mean, *base_mean, 1));
+            for (&value, &base) in percentiles.iter().zip(base_percentiles) {
+                row.add_child(difference_cell(value as f64, base as f64, 0));
+            }
+        }
+        body.add_child(row);
+    }
+
+    table![
+        id!["sensitivity_results"],
+        thead![tr![
+            th![tr("Change")],
+            th![tr("Mean")],
+            PERCENTILES
+                .iter()
+                .map(|pct| th![format!("{:.0}%", pct * 100.0)])
+                .collect::<Vec<_>>(),
+        ]],
+        body,
+    ]
+}
+
+/// Page contents for comparing the current setup with small changes to it.
+pub fn sensitivity_page(state: &SensitivityState, banner: &Banner, goal: &Goal) -> Vec<Node<Msg>> {
+    let issues = validation::validate(goal, banner);
+    vec![
+        subpages::header(),
+        h2![tr("Sensitivity")],
+        p![tr(
+            "How the cost of the current setup changes with one more or one fewer copy, \
+             focus unit of each color, or with focus charges switched. \
+             Every variant uses the same random numbers, so the differences are \
+             mostly due to the change itself."
+        )],
+        p![tr_args(
+            "Current setup: {goal} - {banner}",
+            &[("goal", goal), ("banner", banner)],
+        )],
+        div![
+            label![
+                attrs![At::For => "sensitivity_trials"],
+                tr("Simulations per variant: ")
+            ],
+            input![
+                id!["sensitivity_trials"],
+                input_ev(Ev::Input, |text| match text.parse::<u32>() {
+                    Ok(trials) if trials > 0 => Msg::SensitivityTrialsChange { trials },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Value => state.trials;
+                    At::Min => 1;
+                    At::Step => 1000;
+                ],
+            ],
+            if state.run.is_running() {
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::SensitivityStop),
+                    tr("Stop")
+                ]
+            } else {
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::SensitivityStart),
+                    if validation::has_errors(&issues) {
+                        attrs![
+                            At::Disabled => true;
+                            At::Title => tr("The goal can't be completed on this banner");
+                        ]
+                    } else {
+                        attrs![]
+                    },
+                    tr("Run")
+                ]
+            },
+            if state.run.is_running() {
+                span![
+                    class!["padleft"],
+                    tr_args(
+                        "{done} of {count} variants done",
+                        &[
+                            ("done", &state.run.results.len()),
+                            ("count", &state.run.len())
+                        ],
+                    )
+                ]
+            } else {
+                empty![]
+            },
+        ],
+        if state.run.results.is_empty() {
+            empty![]
+        } else {
+            div![
+                p![tr_args("Results for: {setup}", &[("setup", &state.setup)])],
+                results_table(state)
+            ]
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::goal::GoalPreset;
+
+    #[test]
+    fn variants_change_one_thing() {
+        let banner = Banner {
+            focus_sizes: [1, 0, 2, 1],
+            ..Banner::default()
+        };
+        let goal = Goal::Preset(GoalPreset::RedFocus, 2);
+        let variants = variants(&banner, &goal);
+        let labels: Vec<&str> = variants.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Current setup",
+                "One more copy",
+                "One fewer copy",
+                // Without red focus units the goal can't be completed.
+                "One more red focus unit",
+                "One more blue focus unit",
+                "One more green focus unit",
+                "One fewer green focus unit",
+                "One more colorless focus unit",
+                "One fewer colorless focus unit",
+                "Focus charges off",
+            ]
+        );
+        let copies = |goal: &Goal| goal.as_custom(&banner).goals[0].num_copies;
+        assert_eq!(copies(&variants[1].goal), 3);
+        assert_eq!(copies(&variants[2].goal), 1);
+        assert_eq!(variants[3].banner.focus_sizes, [2, 0, 2, 1]);
+    }
+
+    #[test]
+    fn no_fewer_copies_than_one() {
+        let goal = Goal::Preset(GoalPreset::RedFocus, 1);
+        assert!(with_copies(&goal, &Banner::default(), -1).is_none());
+        assert!(with_copies(&goal, &Banner::default(), 1).is_some());
+    }
+
+    #[test]
+    fn total_goals_change_the_total() {
+        let mut expr = GoalExpr::Group {
+            kind: GoalKind::Total(3),
+            children: vec![GoalExpr::default()],
+        };
+        change_expr_copies(&mut expr, 1);
+        assert!(matches!(
+            expr,
+            GoalExpr::Group {
+                kind: GoalKind::Total(4),
+                ..
+            }
+        ));
+    }
+}
@@ -5,9 +5,9 @@ use rand::distributions::Distribution;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 
-use weighted_choice::{WeightedIndex4, WeightedIndex5};
+use weighted_choice::AliasTable;
 
-use goal::{CustomGoal, GoalKind};
+use goal::{GoalExpr, GoalKind};
 
 /// The results of a pull session.
 struct SessionResult {
@@ -27,7 +27,6 @@ struct PullOrbResult {
 #[derive(Debug)]
 pub struct Sim {
     banner: Banner,
-    goal: CustomGoal,
     tables: RandTables,
     rng: SmallRng,
     goal_data: GoalData,
@@ -37,22 +36,216 @@ pub struct Sim {
 #[derive(Debug, Copy, Clone, Default)]
 struct RandTables {
     pool_sizes: [[u8; 4]; 5],
-    pool_dists: [WeightedIndex5; 26],
-    color_dists: [WeightedIndex4; 5],
+    /// Distributions of the pool and color of an orb, as `pool * 4 + color`,
+    /// indexed by whether a focus charge is active and then by the number of
+    /// rate increases.
+    orb_dists: [[AliasTable<20>; 26]; 2],
 }
 
 /// Scratch space for representing the goal in a way that is faster to work with.
-#[derive(Debug, Clone)]
+/// The goal is compiled into a flat list of nodes in depth-first order, so that
+/// each node's descendants directly follow it.
+#[derive(Debug, Clone, Default)]
 struct GoalData {
-    pub is_fourstar_focus: bool,
+    pub pool_needed: [bool; 5],
     pub color_needed: [bool; 4],
-    pub copies_needed: [Vec<u8>; 4],
+    /// The goal nodes that are targeting each unit, by pool, then by color,
+    /// and then by unit. The 4* focus unit is also the first 5* focus unit of
+    /// its color, so nodes targeting it are in both pools.
+    pub unit_leaves: [[Vec<Vec<usize>>; 4]; 5],
+    /// All of the goal nodes targeting any unit of each pool and color, so
+    /// that they can be checked without going through every unit.
+    pub color_leaves: [[Vec<usize>; 4]; 5],
+    /// The current state of the goal nodes, with the root first.
+    pub nodes: Vec<GoalNode>,
+    /// The state of the goal nodes before any orbs are pulled.
+    pub initial_nodes: Vec<GoalNode>,
+}
+
+/// The progress towards one part of a goal.
+#[derive(Debug, Copy, Clone)]
+struct GoalNode {
+    parent: Option<usize>,
+    /// One past the index of the last node in this node's subtree.
+    end: usize,
+    /// Number of copies, or of completed children, that are still needed.
+    remaining: u32,
+    /// Whether every copy pulled of a unit in this node's subtree counts
+    /// towards it, instead of only completed children.
+    counts_copies: bool,
+    /// Whether the node no longer needs any more progress, either because it
+    /// was completed or because it can't or doesn't need to be.
+    done: bool,
 }
 
 impl GoalData {
+    /// Compiles a goal for the given banner. Parts that can't be completed on
+    /// the banner are left out, and groups only ask for as many children as
+    /// can be completed.
+    fn compile(expr: &GoalExpr, banner: &Banner) -> Self {
+        let mut goal_data = GoalData::default();
+        for (leaves, sizes) in goal_data.unit_leaves.iter_mut().zip(&banner.pool_sizes()) {
+            for (units, &size) in leaves.iter_mut().zip(sizes) {
+                *units = vec![vec![]; size as usize];
+            }
+        }
+        goal_data.add_node(expr, banner, None, false);
+        goal_data.initial_nodes = goal_data.nodes.clone();
+        goal_data.reset();
+        goal_data
+    }
+
+    /// Adds the nodes for `expr` and its descendants.
+    fn add_node(
+        &mut self,
+        expr: &GoalExpr,
+        banner: &Banner,
+        parent: Option<usize>,
+        in_total: bool,
+    ) {
+        let id = self.nodes.len();
+        let mut node = GoalNode {
+            parent,
+            end: id + 1,
+            remaining: 0,
+            counts_copies: false,
+            done: !expr.can_complete(banner, in_total),
+        };
+        self.nodes.push(node);
+        match expr {
+            GoalExpr::Part { part, unit } => {
+                node.counts_copies = true;
+                node.remaining = if in_total {
+                    u32::MAX
+                } else {
+                    part.num_copies as u32
+                };
+                if !node.done {
+                    let color = part.unit_color as usize;
+                    if part.pool == Pool::FourstarFocus {
+                        for &pool in &[Pool::FourstarFocus, Pool::Focus] {
+                            self.unit_leaves[pool as usize][color][0].push(id);
+                            self.color_leaves[pool as usize][color].push(id);
+                        }
+                    } else {
+                        self.unit_leaves[part.pool as usize][color][*unit as usize].push(id);
+                        self.color_leaves[part.pool as usize][color].push(id);
+                    }
+                }
+            }
+            GoalExpr::Group { kind, children } => {
+                let in_total = matches!(kind, GoalKind::Total(_));
+                let mut achievable = 0;
+                for child in children {
+                    if child.can_complete(banner, in_total) {
+                        achievable += 1;
+                    }
+                    self.add_node(child, banner, Some(id), in_total);
+                }
+                node.end = self.nodes.len();
+                node.counts_copies = in_total;
+                node.remaining = match *kind {
+                    GoalKind::Any => 1,
+                    GoalKind::All => achievable,
+                    GoalKind::AtLeast(count) => (count as u32).min(achievable),
+                    GoalKind::Total(count) => count as u32,
+                }
+                .max(1);
+            }
+        }
+        self.nodes[id] = node;
+        if node.done {
+            finish(&mut self.nodes, id);
+        }
+    }
+
+    /// Restores the goal to its state before any orbs were pulled.
+    fn reset(&mut self) {
+        self.nodes.clone_from(&self.initial_nodes);
+        self.update_colors();
+    }
+
+    /// Recalculates which pools and colors can still help to complete the
+    /// goal.
+    fn update_colors(&mut self) {
+        let nodes = &self.nodes;
+        self.pool_needed = [false; 5];
+        self.color_needed = [false; 4];
+        for (pool, colors) in self.color_leaves.iter().enumerate() {
+            for (color, leaves) in colors.iter().enumerate() {
+                if leaves.iter().any(|&leaf| is_needed(nodes, leaf)) {
+                    self.pool_needed[pool] = true;
+                    self.color_needed[color] = true;
+                }
+            }
+        }
+    }
+
+    /// Records a pulled copy of the given unit.
+    fn add_copy(&mut self, pool: Pool, color: Color, unit: usize) {
+        if let Some(leaves) = self.unit_leaves[pool as usize][color as usize].get(unit) {
+            if add_copy_to(&mut self.nodes, leaves) {
+                self.update_colors();
+            }
+        }
+    }
+
     fn is_met(&self) -> bool {
-        self.color_needed == [false, false, false, false]
+        self.nodes[0].done
+    }
+}
+
+/// Marks a node and all of its descendants as done.
+fn finish(nodes: &mut [GoalNode], id: usize) {
+    let end = nodes[id].end;
+    for node in &mut nodes[id..end] {
+        node.done = true;
+    }
+}
+
+/// Whether copies of the unit targeted by a leaf node still help to complete
+/// the goal. A completed leaf can still count towards a group above it that
+/// counts every copy.
+fn is_needed(nodes: &[GoalNode], leaf: usize) -> bool {
+    let mut id = Some(leaf);
+    while let Some(node) = id.map(|id| &nodes[id]) {
+        if node.counts_copies && !node.done {
+            return true;
+        }
+        id = node.parent;
+    }
+    false
+}
+
+/// Records a pulled copy of the unit targeted by the given leaf nodes, and
+/// returns whether any nodes were completed.
+fn add_copy_to(nodes: &mut [GoalNode], leaves: &[usize]) -> bool {
+    let mut any_finished = false;
+    for &leaf in leaves {
+        // Walk up from the leaf, counting the copy for every node that counts
+        // copies and each newly completed child for the others. This goes on
+        // past completed nodes, since a group above them may count every copy.
+        let mut id = leaf;
+        let mut child_finished = false;
+        loop {
+            let node = &mut nodes[id];
+            if !node.done && (node.counts_copies || child_finished) {
+                node.remaining -= 1;
+                child_finished = node.remaining == 0;
+                if child_finished {
+                    finish(nodes, id);
+                    any_finished = true;
+                }
+            } else {
+                child_finished = false;
+            }
+            match nodes[id].parent {
+                Some(parent) => id = parent,
+                None => break,
+            }
+        }
     }
+    any_finished
 }
 
 impl Sim {
@@ -62,59 +255,50 @@ impl Sim {
     pub fn new(banner: Banner, goal: Goal) -> Self {
         let mut sim = Sim {
             banner,
-            goal: goal.as_custom(&banner),
             tables: RandTables::default(),
             rng: SmallRng::from_entropy(),
-            goal_data: GoalData {
-                is_fourstar_focus: banner.fourstar_focus.is_some(),
-                color_needed: [false; 4],
-                copies_needed: [vec![], vec![], vec![], vec![]],
-            },
+            goal_data: GoalData::compile(&goal.as_expr(&banner), &banner),
         };
         sim.init_probability_tables();
         sim
     }
 
+    /// Creates a simulator whose random numbers are determined by `seed`, so
+    /// that its results can be reproduced.
+    pub fn with_seed(banner: Banner, goal: Goal, seed: u64) -> Self {
+        let mut sim = Sim::new(banner, goal);
+        sim.rng = SmallRng::seed_from_u64(seed);
+        sim
+    }
+
     /// Initializes the precalculated tables used for fast random sampling.
     fn init_probability_tables(&mut self) {
-        self.tables.pool_sizes = [
-            [0, 0, 0, 0],
-            [26, 19, 14, 17],
-            [0, 0, 0, 0],
-            [45, 46, 37, 50],
-            [45, 46, 37, 50],
-        ];
-        for i in 0..4 {
-            self.tables.pool_sizes[0][i] = self.banner.focus_sizes[i].max(0) as u8;
-        }
-        if let Some(color) = self.banner.fourstar_focus {
-            self.tables.pool_sizes[2][color as usize] = 1;
-        }
+        self.tables.pool_sizes = self.banner.pool_sizes();
 
-        for color in 0..5 {
-            self.tables.color_dists[color] = WeightedIndex4::new(self.tables.pool_sizes[color]);
-        }
-
-        for pity_incr in 0..26 {
-            self.tables.pool_dists[pity_incr] =
-                WeightedIndex5::new(self.probabilities(pity_incr as u32));
-        }
-    }
-
-    // Initializes the internal representation of a goal.
-    fn init_goal_data(&mut self) {
-        self.goal_data.color_needed = [false, false, false, false];
-        self.goal_data.is_fourstar_focus = false;
-        for i in 0..4 {
-            self.goal_data.copies_needed[i].clear();
-        }
-        for &goal in &self.goal.goals {
-            self.goal_data.copies_needed[goal.unit_color as usize].push(goal.num_copies);
-            self.goal_data.color_needed[goal.unit_color as usize] = true;
-            if goal.four_star {
-                self.goal_data.is_fourstar_focus = true;
+        let mut orb_dists = [[AliasTable::default(); 26]; 2];
+        for (focus_charge_active, dists) in orb_dists.iter_mut().enumerate() {
+            for (pity_incr, dist) in dists.iter_mut().enumerate() {
+                let mut rates = self.probabilities(pity_incr as u32);
+                if focus_charge_active == 1 {
+                    rates[Pool::Focus as usize] += rates[Pool::Fivestar as usize];
+                    rates[Pool::Fivestar as usize] = 0.0;
+                }
+                // Split each pool's rate between its colors by their numbers
+                // of units.
+                let mut weights = [0.0; 20];
+                for (pool, sizes) in self.tables.pool_sizes.iter().enumerate() {
+                    let pool_total: u8 = sizes.iter().sum();
+                    for (color, &size) in sizes.iter().enumerate() {
+                        if size > 0 {
+                            weights[pool * 4 + color] =
+                                rates[pool] as f64 * size as f64 / pool_total as f64;
+                        }
+                    }
+                }
+                *dist = AliasTable::new(weights);
             }
         }
+        self.tables.orb_dists = orb_dists;
     }
 
     /// Simulates until reaching the current goal, then returns # of orbs used.
@@ -122,7 +306,7 @@ impl Sim {
         let mut pity_count = 0;
         let mut orb_count = 0;
         let mut focus_charges = 0;
-        self.init_goal_data();
+        self.goal_data.reset();
         loop {
             let pity_incr = pity_count / 5;
             let samples = [
@@ -191,11 +375,8 @@ impl Sim {
 
     /// Evaluates the result of selecting the given sample.
     fn pull_orb(&mut self, sample: (Pool, Color)) -> PullOrbResult {
-        let color = sample.1;
-        if sample.0 == Pool::Threestar
-            || sample.0 == Pool::Fourstar
-            || sample.0 == Pool::Fivestar
-            || (sample.0 == Pool::FourstarFocus && !self.goal_data.is_fourstar_focus)
+        let (pool, color) = sample;
+        if !self.goal_data.pool_needed[pool as usize]
             || !self.goal_data.color_needed[color as usize]
         {
             return PullOrbResult {
@@ -203,24 +384,11 @@ impl Sim {
                 got_non_focus: sample.0 == Pool::Fivestar,
             };
         }
-        let focus_count = self.banner.focus_sizes[color as usize];
-        let which_unit = if sample.0 == Pool::FourstarFocus {
-            0
-        } else {
-            self.rng.gen::<usize>() % focus_count as usize
-        };
-        if which_unit < self.goal_data.copies_needed[color as usize].len() {
-            if self.goal_data.copies_needed[color as usize][which_unit] > 1 {
-                self.goal_data.copies_needed[color as usize][which_unit] -= 1;
-            } else {
-                self.goal_data.copies_needed[color as usize].remove(which_unit);
-                if self.goal.kind == GoalKind::Any {
-                    self.goal_data.color_needed = [false, false, false, false];
-                } else if self.goal_data.copies_needed[color as usize].len() == 0 {
-                    self.goal_data.color_needed[color as usize] = false;
-                }
-            }
-        }
+        // Scales a random number to the pool size, which is faster than taking
+        // the remainder.
+        let pool_size = self.tables.pool_sizes[pool as usize][color as usize];
+        let which_unit = ((self.rng.gen::<u32>() as u64 * pool_size as u64) >> 32) as usize;
+        self.goal_data.add_copy(pool, color, which_unit);
         PullOrbResult {
             got_focus: sample.0 == Pool::Focus,
             got_non_focus: sample.0 == Pool::Fivestar,
@@ -242,14 +410,10 @@ impl Sim {
     /// Chooses a weighted random unit from the summoning pool. `pity_incr` is the
     /// number of times that the 5* rates have increased by 0.5% total.
     fn sample(&mut self, pity_incr: u32, focus_charge_active: bool) -> (Pool, Color) {
-        let pool = self.tables.pool_dists[pity_incr as usize].sample(&mut self.rng) as u8;
-        let mut pool = Pool::try_from(pool).unwrap();
-        if focus_charge_active && pool == Pool::Fivestar {
-            pool = Pool::Focus;
-        }
-
-        let color = self.tables.color_dists[pool as usize].sample(&mut self.rng) as u8;
-        let color = Color::try_from(color).unwrap();
+        let orb = self.tables.orb_dists[focus_charge_active as usize][pity_incr as usize]
+            .sample(&mut self.rng) as u8;
+        let pool = Pool::try_from(orb / 4).unwrap();
+        let color = Color::try_from(orb % 4).unwrap();
         (pool, color)
     }
 
@@ -269,10 +433,14 @@ impl Sim {
         probabilities[Pool::Focus as usize] += pity_pct * focus_ratio;
         probabilities[Pool::Fivestar as usize] += pity_pct * (1.0 - focus_ratio);
 
-        let lower_ratio = bases[Pool::Fourstar as usize]
-            / (bases[Pool::Fourstar as usize] + bases[Pool::Threestar as usize]);
-        probabilities[Pool::Fourstar as usize] -= pity_pct * lower_ratio;
-        probabilities[Pool::Threestar as usize] -= pity_pct * (1.0 - lower_ratio);
+        // The rates of everything below 5* go down in proportion to their size,
+        // including the 4* focus unit, so that they reach 0 along with each
+        // other once a 5* unit is guaranteed.
+        let lower_pools = [Pool::FourstarFocus, Pool::Fourstar, Pool::Threestar];
+        let lower_total: f32 = lower_pools.iter().map(|&pool| bases[pool as usize]).sum();
+        for &pool in &lower_pools {
+            probabilities[pool as usize] -= pity_pct * bases[pool as usize] / lower_total;
+        }
         probabilities
     }
 
@@ -295,3 +463,183 @@ impl Sim {
         }
     }
 }
+
+#[cfg(test)]
+mod reference_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    use goal::GoalPreset;
+    use strum::IntoEnumIterator;
+
+    /// Any banner that can be chosen in the banner settings.
+    fn any_banner() -> impl Strategy<Value = Banner> {
+        (
+            prop::array::uniform4(0i8..=4),
+            prop::sample::select(vec![(3, 3), (5, 3), (8, 0), (4, 2), (6, 0)]),
+            any::<bool>(),
+            prop::option::of(0u8..4),
+        )
+            .prop_map(
+                |(focus_sizes, starting_rates, focus_charges, fourstar_focus)| Banner {
+                    focus_sizes,
+                    starting_rates,
+                    focus_charges,
+                    fourstar_focus: fourstar_focus.map(|color| Color::try_from(color).unwrap()),
+                },
+            )
+    }
+
+    /// Any preset goal that can be run on the banner, along with the banner.
+    fn any_runnable_preset() -> impl Strategy<Value = (Banner, Goal)> {
+        let presets: Vec<GoalPreset> = GoalPreset::iter().collect();
+        (any_banner(), prop::sample::select(presets), 1u8..=3).prop_filter_map(
+            "goal must be available on the banner",
+            |(banner, preset, count)| {
+                if preset.is_available(&banner) {
+                    Some((banner, Goal::Preset(preset, count)))
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Whether `cost` is the total cost of some sequence of summoning sessions.
+    fn is_session_total(cost: u32) -> bool {
+        let mut reachable = vec![false; cost as usize + 1];
+        reachable[0] = true;
+        for total in 1..=cost {
+            reachable[total as usize] = (1..=5).any(|count| {
+                let session = Sim::orb_cost(count);
+                session <= total && reachable[(total - session) as usize]
+            });
+        }
+        reachable[cost as usize]
+    }
+
+    #[test]
+    fn orb_cost_is_monotone() {
+        for count in 1..5 {
+            assert!(Sim::orb_cost(count) < Sim::orb_cost(count + 1));
+        }
+    }
+
+    #[test]
+    fn orb_cost_gets_cheaper_per_orb() {
+        for count in 1..5 {
+            let per_orb = |count| Sim::orb_cost(count) as f32 / count as f32;
+            assert!(per_orb(count + 1) < per_orb(count));
+        }
+    }
+
+    #[test]
+    fn groups_need_enough_completable_children() {
+        let part = |unit_color| GoalExpr::Part {
+            part: GoalPart {
+                unit_color,
+                num_copies: 1,
+                pool: Pool::Focus,
+            },
+            unit: 0,
+        };
+        let red_and_blue = GoalExpr::Group {
+            kind: GoalKind::All,
+            children: vec![part(Color::Red), part(Color::Blue)],
+        };
+        let expr = GoalExpr::Group {
+            kind: GoalKind::Any,
+            children: vec![red_and_blue.clone(), part(Color::Colorless)],
+        };
+        let banner = Banner {
+            focus_sizes: [1, 0, 1, 1],
+            ..Banner::default()
+        };
+        assert!(!red_and_blue.can_complete(&banner, false));
+        assert!(expr.can_complete(&banner, false));
+
+        let mut goal_data = GoalData::compile(&expr, &banner);
+        goal_data.add_copy(Pool::Focus, Color::Red, 0);
+        assert!(!goal_data.is_met());
+        assert!(!goal_data.color_needed[Color::Red as usize]);
+        goal_data.add_copy(Pool::Focus, Color::Colorless, 0);
+        assert!(goal_data.is_met());
+    }
+
+    #[test]
+    fn total_counts_copies_of_completed_subgroups() {
+        let part = |unit_color| GoalExpr::Part {
+            part: GoalPart {
+                unit_color,
+                num_copies: 1,
+                pool: Pool::Focus,
+            },
+            unit: 0,
+        };
+        let expr = GoalExpr::Group {
+            kind: GoalKind::Total(3),
+            children: vec![GoalExpr::Group {
+                kind: GoalKind::All,
+                children: vec![part(Color::Red), part(Color::Blue)],
+            }],
+        };
+        let mut goal_data = GoalData::compile(&expr, &Banner::default());
+        for _ in 0..2 {
+            goal_data.add_copy(Pool::Focus, Color::Red, 0);
+            assert!(!goal_data.is_met());
+            assert!(goal_data.color_needed[Color::Red as usize]);
+        }
+        goal_data.add_copy(Pool::Focus, Color::Red, 0);
+        assert!(goal_data.is_met());
+    }
+
+    #[test]
+    fn seeded_sims_are_reproducible() {
+        let banner = Banner::default();
+        let goal = Goal::Preset(GoalPreset::AllFocus, 1);
+        let mut first = Sim::with_seed(banner, goal.clone(), 7);
+        let mut second = Sim::with_seed(banner, goal, 7);
+        for _ in 0..100 {
+            assert_eq!(first.roll_until_goal(), second.roll_until_goal());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn probabilities_sum_to_100(banner in any_banner(), pity_incr in 0u32..26) {
+            let sim = Sim::new(banner, Goal::default());
+            let probabilities = sim.probabilities(pity_incr);
+            let total: f32 = probabilities.iter().sum();
+            prop_assert!((total - 100.0).abs() < 1e-3, "{:?}", probabilities);
+            for &probability in &probabilities {
+                prop_assert!(probability > -1e-3, "{:?}", probabilities);
+            }
+        }
+
+        #[test]
+        fn five_star_rates_increase_with_pity(banner in any_banner(), pity_incr in 0u32..25) {
+            let sim = Sim::new(banner, Goal::default());
+            let before = sim.probabilities(pity_incr);
+            let after = sim.probabilities(pity_incr + 1);
+            let fivestar = |p: [f32; 5]| p[Pool::Focus as usize] + p[Pool::Fivestar as usize];
+            prop_assert!(fivestar(after) > fivestar(before));
+        }
+
+        #[test]
+        fn roll_until_goal_terminates_with_session_costs(
+            (banner, goal) in any_runnable_preset(),
+            seed in any::<u64>(),
+        ) {
+            let mut sim = Sim::with_seed(banner, goal, seed);
+            for _ in 0..10 {
+                let cost = sim.roll_until_goal();
+                prop_assert!(cost >= Sim::orb_cost(1));
+                prop_assert!(is_session_total(cost), "{} orbs", cost);
+            }
+        }
+    }
+}
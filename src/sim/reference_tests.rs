@@ -0,0 +1,423 @@
+//! Statistical checks of the simulator against known results.
+//!
+//! Each check runs a large number of simulations and compares the distribution
+//! of their orb costs with an expected distribution, failing if a chi-square
+//! test says that they differ. This takes a while, so the checks are ignored by
+//! default. Run them with `cargo make validate_sim`.
+//!
+//! The expected values for the reference scenarios were measured from
+//! `REFERENCE_SAMPLES` simulations of each one. After an intentional change to
+//! the simulation, print new ones with
+//! `cargo test --release print_references -- --ignored --nocapture` and
+//! replace `REFERENCES` with the output.
+
+use super::*;
+
+use goal::{CustomGoal, GoalPart, GoalPreset};
+
+/// Number of simulations run for each check.
+const SAMPLES: usize = 200_000;
+
+/// Number of simulations that the stored reference values were measured from.
+const REFERENCE_SAMPLES: usize = 10_000_000;
+
+/// The standard normal quantile that the checks fail beyond, giving a false
+/// failure rate of about one in a thousand for each check.
+const Z_LIMIT: f64 = 3.09;
+
+/// Percentiles that the reference distributions are split at.
+const PERCENTILES: [f64; 6] = [0.1, 0.25, 0.5, 0.75, 0.9, 0.99];
+
+/// A banner and goal to check the simulator with.
+struct Scenario {
+    name: &'static str,
+    banner: Banner,
+    goal: Goal,
+}
+
+/// Stored results of simulating a scenario.
+struct Reference {
+    name: &'static str,
+    mean: f64,
+    /// Points on the distribution of orb costs, as an orb count and the
+    /// fraction of results that cost no more than that.
+    cdf: &'static [(u32, f64)],
+}
+
+/// The scenarios that have stored reference values. These cover pity, the
+/// partial pity reset from general 5* units, focus charges, 4* focus units,
+/// and goals that need several units.
+fn scenarios() -> Vec<Scenario> {
+    let part = |unit_color, num_copies| GoalPart {
+        unit_color,
+        num_copies,
+        pool: Pool::Focus,
+    };
+    vec![
+        Scenario {
+            name: "3%/3%, any focus",
+            banner: Banner {
+                focus_charges: false,
+                ..Banner::default()
+            },
+            goal: Goal::Preset(GoalPreset::AnyFocus, 1),
+        },
+        Scenario {
+            name: "3%/3%, all focus",
+            banner: Banner {
+                focus_charges: false,
+                ..Banner::default()
+            },
+            goal: Goal::Preset(GoalPreset::AllFocus, 1),
+        },
+        Scenario {
+            name: "3%/3% with focus charges, red focus x4",
+            banner: Banner::default(),
+            goal: Goal::Preset(GoalPreset::RedFocus, 4),
+        },
+        Scenario {
+            name: "8%/0% legendary, red focus",
+            banner: Banner {
+                focus_sizes: [3, 3, 3, 3],
+                starting_rates: (8, 0),
+                focus_charges: false,
+                fourstar_focus: None,
+            },
+            goal: Goal::Preset(GoalPreset::RedFocus, 1),
+        },
+        Scenario {
+            name: "3%/3% with red 4* focus, red 4* focus",
+            banner: Banner {
+                fourstar_focus: Some(Color::Red),
+                ..Banner::default()
+            },
+            goal: Goal::Preset(GoalPreset::RedFourstarFocus, 1),
+        },
+        Scenario {
+            name: "5%/3%, red and blue focus",
+            banner: Banner {
+                focus_sizes: [2, 1, 1, 0],
+                starting_rates: (5, 3),
+                focus_charges: false,
+                fourstar_focus: None,
+            },
+            goal: Goal::Custom(CustomGoal {
+                kind: GoalKind::All,
+                goals: vec![part(Color::Red, 1), part(Color::Blue, 1)],
+            }),
+        },
+    ]
+}
+
+/// Reference values for each of the `scenarios`, in the same order.
+const REFERENCES: &[Reference] = &[
+    Reference {
+        name: "3%/3%, any focus",
+        mean: 113.161,
+        cdf: &[
+            (17, 0.114716),
+            (40, 0.270849),
+            (85, 0.505996),
+            (157, 0.753086),
+            (245, 0.900466),
+            (457, 0.990142),
+        ],
+    },
+    Reference {
+        name: "3%/3%, all focus",
+        mean: 515.146,
+        cdf: &[
+            (247, 0.100825),
+            (345, 0.251096),
+            (481, 0.501244),
+            (648, 0.750682),
+            (827, 0.900128),
+            (1208, 0.990004),
+        ],
+    },
+    Reference {
+        name: "3%/3% with focus charges, red focus x4",
+        mean: 590.059,
+        cdf: &[
+            (300, 0.101034),
+            (412, 0.250073),
+            (562, 0.501177),
+            (736, 0.750436),
+            (916, 0.900311),
+            (1281, 0.990009),
+        ],
+    },
+    Reference {
+        name: "8%/0% legendary, red focus",
+        mean: 192.392,
+        cdf: &[
+            (24, 0.101958),
+            (61, 0.253131),
+            (137, 0.501346),
+            (265, 0.751239),
+            (433, 0.900171),
+            (856, 0.990046),
+        ],
+    },
+    Reference {
+        name: "3%/3% with red 4* focus, red 4* focus",
+        mean: 39.522,
+        cdf: &[
+            (5, 0.108594),
+            (14, 0.278310),
+            (28, 0.503742),
+            (54, 0.752704),
+            (87, 0.901195),
+            (168, 0.990102),
+        ],
+    },
+    Reference {
+        name: "5%/3%, red and blue focus",
+        mean: 193.318,
+        cdf: &[
+            (58, 0.102552),
+            (99, 0.253395),
+            (165, 0.501183),
+            (258, 0.751946),
+            (365, 0.900032),
+            (613, 0.990037),
+        ],
+    },
+];
+
+/// Runs `count` simulations, returning their orb costs in increasing order.
+fn sorted_costs(sim: &mut Sim, count: usize) -> Vec<u32> {
+    let mut costs: Vec<u32> = (0..count).map(|_| sim.roll_until_goal()).collect();
+    costs.sort_unstable();
+    costs
+}
+
+fn mean(costs: &[u32]) -> f64 {
+    costs.iter().map(|&cost| cost as f64).sum::<f64>() / costs.len() as f64
+}
+
+fn variance(costs: &[u32]) -> f64 {
+    let mean = mean(costs);
+    costs
+        .iter()
+        .map(|&cost| (cost as f64 - mean).powi(2))
+        .sum::<f64>()
+        / (costs.len() - 1) as f64
+}
+
+/// Fraction of the sorted costs that are no more than `orbs`.
+fn fraction_at_most(costs: &[u32], orbs: u32) -> f64 {
+    costs.partition_point(|&cost| cost <= orbs) as f64 / costs.len() as f64
+}
+
+/// The chi-square value above which `degrees` degrees of freedom fail,
+/// using the Wilson-Hilferty approximation.
+fn chi_square_limit(degrees: usize) -> f64 {
+    let k = degrees as f64;
+    let spread = 2.0 / (9.0 * k);
+    k * (1.0 - spread + Z_LIMIT * spread.sqrt()).powi(3)
+}
+
+/// Checks the sorted costs against an expected mean and variance of the mean,
+/// and against the expected cumulative distribution `cdf`.
+fn check_costs(
+    name: &str,
+    costs: &[u32],
+    expected_mean: f64,
+    mean_variance: f64,
+    cdf: &[(u32, f64)],
+) {
+    let n = costs.len() as f64;
+
+    let z = (mean(costs) - expected_mean) / mean_variance.sqrt();
+    assert!(
+        z.abs() < Z_LIMIT,
+        "{}: mean of {:.2} orbs is {:.1} standard errors away from {:.2}",
+        name,
+        mean(costs),
+        z,
+        expected_mean
+    );
+
+    // Split the results into bins at each point of the expected distribution.
+    let mut chi_square = 0.0;
+    let mut previous = (0.0, 0.0);
+    let bounds = cdf
+        .iter()
+        .map(|&(orbs, expected)| (fraction_at_most(costs, orbs), expected));
+    for (observed, expected) in bounds.chain(std::iter::once((1.0, 1.0))) {
+        let observed_count = (observed - previous.0) * n;
+        let expected_count = (expected - previous.1) * n;
+        chi_square += (observed_count - expected_count).powi(2) / expected_count;
+        previous = (observed, expected);
+    }
+    let limit = chi_square_limit(cdf.len());
+    assert!(
+        chi_square < limit,
+        "{}: distribution differs from the expected one, with chi-square {:.1} > {:.1}",
+        name,
+        chi_square,
+        limit
+    );
+}
+
+#[test]
+#[ignore]
+fn matches_reference_values() {
+    let scenarios = scenarios();
+    assert_eq!(
+        scenarios.len(),
+        REFERENCES.len(),
+        "REFERENCES is out of date"
+    );
+    for (seed, (scenario, reference)) in scenarios.iter().zip(REFERENCES).enumerate() {
+        assert_eq!(scenario.name, reference.name, "REFERENCES is out of date");
+        let mut sim = Sim::with_seed(scenario.banner, scenario.goal.clone(), seed as u64);
+        let costs = sorted_costs(&mut sim, SAMPLES);
+        // The reference values were measured too, so they have some error of
+        // their own.
+        let mean_variance =
+            variance(&costs) * (1.0 / SAMPLES as f64 + 1.0 / REFERENCE_SAMPLES as f64);
+        check_costs(
+            scenario.name,
+            &costs,
+            reference.mean,
+            mean_variance,
+            reference.cdf,
+        );
+    }
+}
+
+/// Prints reference values for the current simulator in the form used by
+/// `REFERENCES`.
+#[test]
+#[ignore]
+fn print_references() {
+    println!("const REFERENCES: &[Reference] = &[");
+    for scenario in scenarios() {
+        let mut sim = Sim::new(scenario.banner, scenario.goal.clone());
+        let costs = sorted_costs(&mut sim, REFERENCE_SAMPLES);
+        println!("    Reference {{");
+        println!("        name: {:?},", scenario.name);
+        println!("        mean: {:.3},", mean(&costs));
+        println!("        cdf: &[");
+        let mut points: Vec<u32> = PERCENTILES
+            .iter()
+            .map(|&percentile| costs[(percentile * costs.len() as f64) as usize])
+            .collect();
+        points.dedup();
+        for orbs in points {
+            println!(
+                "            ({}, {:.6}),",
+                orbs,
+                fraction_at_most(&costs, orbs)
+            );
+        }
+        println!("        ],");
+        println!("    }},");
+    }
+    println!("];");
+}
+
+/// Compares a simple case with no pity against its exact distribution. The
+/// banner has a single red focus unit at 3%, and a red stone is chosen
+/// whenever one appears, so every session is independent of the ones before.
+#[test]
+#[ignore]
+fn single_focus_without_pity_matches_exact_distribution() {
+    let banner = Banner {
+        focus_sizes: [1, 0, 0, 0],
+        starting_rates: (3, 3),
+        focus_charges: false,
+        fourstar_focus: None,
+    };
+    let mut sim = Sim::with_seed(banner, Goal::Preset(GoalPreset::RedFocus, 1), 0);
+    let rates = sim.probabilities(0);
+    assert_eq!(rates[Pool::Focus as usize], 3.0);
+    let no_pity = sim.tables.orb_dists[0][0];
+    sim.tables.orb_dists = [[no_pity; 26]; 2];
+
+    // Chance of a stone being red, and of a red stone being the focus unit.
+    let pool_sizes = banner.pool_sizes();
+    let red: f64 = rates
+        .iter()
+        .zip(&pool_sizes)
+        .filter(|(_, sizes)| sizes.iter().any(|&size| size > 0))
+        .map(|(&rate, sizes)| {
+            let total: u8 = sizes.iter().sum();
+            rate as f64 / 100.0 * sizes[Color::Red as usize] as f64 / total as f64
+        })
+        .sum();
+    let focus = rates[Pool::Focus as usize] as f64 / 100.0 / red;
+
+    // Chances of a session costing each number of orbs, split by whether it
+    // ends with the focus unit.
+    let mut failure = [0.0; 21];
+    let mut success = [0.0; 21];
+    for reds in 0..=5 {
+        let binomial = [1.0, 5.0, 10.0, 10.0, 5.0, 1.0][reds];
+        let chance = binomial * red.powi(reds as i32) * (1.0 - red).powi(5 - reds as i32);
+        if reds == 0 {
+            failure[Sim::orb_cost(1) as usize] += chance;
+            continue;
+        }
+        for pulled in 1..=reds {
+            let first_focus = (1.0 - focus).powi(pulled as i32 - 1) * focus;
+            success[Sim::orb_cost(pulled as u32) as usize] += chance * first_focus;
+        }
+        failure[Sim::orb_cost(reds as u32) as usize] += chance * (1.0 - focus).powi(reds as i32);
+    }
+
+    // Chances of the total cost, as any number of failed sessions followed by
+    // a successful one.
+    const MAX_ORBS: usize = 5000;
+    let mut failures_total = vec![0.0; MAX_ORBS + 1];
+    let mut total = vec![0.0; MAX_ORBS + 1];
+    failures_total[0] = 1.0;
+    for orbs in 0..=MAX_ORBS {
+        for cost in 1..=20.min(orbs) {
+            failures_total[orbs] += failure[cost] * failures_total[orbs - cost];
+            total[orbs] += success[cost] * failures_total[orbs - cost];
+        }
+    }
+    let covered: f64 = total.iter().sum();
+    assert!(
+        1.0 - covered < 1e-9,
+        "exact distribution is cut off too early"
+    );
+
+    let exact_mean: f64 = total
+        .iter()
+        .enumerate()
+        .map(|(orbs, p)| orbs as f64 * p)
+        .sum();
+    let exact_variance: f64 = total
+        .iter()
+        .enumerate()
+        .map(|(orbs, p)| (orbs as f64 - exact_mean).powi(2) * p)
+        .sum();
+    let mut cdf = vec![];
+    let mut cumulative = 0.0;
+    let mut next_percentile = PERCENTILES.iter().peekable();
+    for (orbs, p) in total.iter().enumerate() {
+        cumulative += p;
+        if let Some(&&percentile) = next_percentile.peek() {
+            if cumulative >= percentile {
+                cdf.push((orbs as u32, cumulative));
+                while next_percentile
+                    .next_if(|&&next| cumulative >= next)
+                    .is_some()
+                {}
+            }
+        }
+    }
+
+    let costs = sorted_costs(&mut sim, SAMPLES);
+    check_costs(
+        "single focus without pity",
+        &costs,
+        exact_mean,
+        exact_variance / SAMPLES as f64,
+        &cdf,
+    );
+}
@@ -1,50 +1,235 @@
-use crate::counter::Counter;
+use crate::histogram::Histogram;
 
 /// Calculates the given percentile of the data. `pct` is in the range [0.0, 1.0]
-pub fn percentile(data: &Counter, pct: f32) -> u32 {
-    percentiles(data, &[pct])[0]
+pub fn percentile(data: &Histogram, pct: f32) -> u32 {
+    debug_assert!((0.0..=1.0).contains(&pct));
+
+    let total = data.total();
+    if total == 0 {
+        return 0;
+    }
+    // 100% (or close enough for rounding errors) never has more than that
+    // fraction of the data at or below it, so it falls through to the largest
+    // value.
+    data.first_value_where(|below| below as f32 / total as f32 > pct)
 }
 
-/// Calculates multiple percentiles in bulk. `pcts` must be sorted in ascending order
-/// with every value in the range [0.0, 1.0].
-pub fn percentiles(data: &Counter, pcts: &[f32]) -> Vec<u32> {
-    debug_assert!(pcts.iter().all(|&x| x >= 0.0 && x <= 1.0));
-    debug_assert!((0..pcts.len() - 1).all(|idx| pcts[idx + 1] >= pcts[idx]));
+/// Calculates multiple percentiles in bulk. Every value of `pcts` is in the
+/// range [0.0, 1.0].
+pub fn percentiles(data: &Histogram, pcts: &[f32]) -> Vec<u32> {
+    pcts.iter().map(|&pct| percentile(data, pct)).collect()
+}
 
-    let total: u32 = data.iter().sum();
-    let mut results = vec![0; pcts.len()];
+/// Gives the total number of samples in the data.
+pub fn count(data: &Histogram) -> u64 {
+    data.total()
+}
+
+/// Calculates the arithmetic mean of the data. Returns 0 if there is no data.
+pub fn mean(data: &Histogram) -> f64 {
+    let total = count(data);
+    if total == 0 {
+        return 0.0;
+    }
+    let sum: f64 = data
+        .iter()
+        .map(|(value, num)| value as f64 * num as f64)
+        .sum();
+    sum / total as f64
+}
 
+/// Calculates the population variance of the data. Returns 0 if there is no data.
+pub fn variance(data: &Histogram) -> f64 {
+    let total = count(data);
     if total == 0 {
-        return results;
+        return 0.0;
     }
+    let mean = mean(data);
+    let sum_sq: f64 = data
+        .iter()
+        .map(|(value, num)| (value as f64 - mean).powi(2) * num as f64)
+        .sum();
+    sum_sq / total as f64
+}
 
-    let mut accum_total = 0;
-    let mut out_idx = 0;
-    for value in 0..data.len() as u32 {
-        accum_total += data[value];
-        while out_idx < results.len() && accum_total as f32 / total as f32 > pcts[out_idx] {
-            results[out_idx] = value;
-            out_idx += 1;
+/// Calculates the standard deviation of the data. Returns 0 if there is no data.
+pub fn std_dev(data: &Histogram) -> f64 {
+    variance(data).sqrt()
+}
+
+/// Finds the most common value in the data. Ties go to the smallest value, and
+/// returns 0 if there is no data.
+pub fn mode(data: &Histogram) -> u32 {
+    let mut best = (0, 0);
+    for (value, num) in data.iter() {
+        if num > best.1 {
+            best = (value, num);
         }
     }
+    best.0
+}
+
+/// Calculates the average of the highest `frac` portion of the data, e.g. the
+/// average cost of the unluckiest 10% of sessions for `frac = 0.1`. `frac` is
+/// in the range (0.0, 1.0]. Returns 0 if there is no data.
+pub fn expected_shortfall(data: &Histogram, frac: f32) -> f64 {
+    debug_assert!(frac > 0.0 && frac <= 1.0);
 
-    if out_idx == results.len() {
-        return results;
+    let total = count(data);
+    if total == 0 {
+        return 0.0;
     }
 
-    // The remaining values in pcts are 100% (or close enough for rounding errors)
-    // if it didn't already finish, so grab the last non-zero value and fill the
-    // rest of the results.
-    for &value in data.iter().rev() {
-        if value > 0 {
-            for i in out_idx..results.len() {
-                results[i] = value;
-            }
-            return results;
+    // The tail usually doesn't line up with a whole number of samples, so the
+    // boundary value is counted fractionally.
+    let tail_size = (total as f64 * frac as f64).max(1.0);
+    let mut remaining = tail_size;
+    let mut sum = 0.0;
+    for (value, num) in data.iter().rev() {
+        if remaining <= 0.0 {
+            break;
         }
+        let taken = (num as f64).min(remaining);
+        sum += value as f64 * taken;
+        remaining -= taken;
     }
+    sum / tail_size
+}
+
+/// Calculates the fraction of the data that is strictly greater than `value`.
+/// Returns 0 if there is no data.
+pub fn prob_exceeding(data: &Histogram, value: u32) -> f32 {
+    let total = count(data);
+    if total == 0 {
+        return 0.0;
+    }
+    let above = total - data.count_at_most(value);
+    (above as f64 / total as f64) as f32
+}
+
+/// Estimates the standard error of the given percentile of the data, in the
+/// same units as the data. A percentile estimated from `n` samples has about
+/// the same uncertainty as the fraction of samples below it, so the error is
+/// taken as half the distance between the percentiles one standard error of
+/// that fraction above and below `pct`. Returns 0 if there is no data.
+pub fn percentile_std_error(data: &Histogram, pct: f32) -> f64 {
+    let total = count(data);
+    if total == 0 {
+        return 0.0;
+    }
+    let pct = pct as f64;
+    let spread = (pct * (1.0 - pct) / total as f64).sqrt();
+    let bounds = percentiles(
+        data,
+        &[
+            (pct - spread).max(0.0) as f32,
+            (pct + spread).min(1.0) as f32,
+        ],
+    );
+    (bounds[1] - bounds[0]) as f64 / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
 
-    // It would have returned early if all entries were zero, so the above loop
-    // is guaranteed to find something and exit.
-    unreachable!()
+    /// A histogram with at least one sample in it.
+    fn any_histogram() -> impl Strategy<Value = Histogram> {
+        prop::collection::vec(0u32..20, 1..60)
+            .prop_filter("histogram must not be empty", |counts| {
+                counts.iter().any(|&x| x > 0)
+            })
+            .prop_map(|counts| {
+                let mut histogram = Histogram::default();
+                for (value, &count) in counts.iter().enumerate() {
+                    histogram.add_count(value as u32, count as u64);
+                }
+                histogram
+            })
+    }
+
+    /// A sorted list of percentiles.
+    fn sorted_pcts() -> impl Strategy<Value = Vec<f32>> {
+        prop::collection::vec(0.0f32..=1.0, 1..10).prop_map(|mut pcts| {
+            pcts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            pcts
+        })
+    }
+
+    #[test]
+    fn percentiles_of_empty_data_are_zero() {
+        assert_eq!(
+            percentiles(&Histogram::default(), &[0.0, 0.5, 1.0]),
+            vec![0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn percentiles_of_known_data() {
+        let mut data = Histogram::default();
+        for value in 1..=100 {
+            data.record(value);
+        }
+        assert_eq!(percentile(&data, 0.0), 1);
+        assert_eq!(percentile(&data, 0.5), 51);
+        assert_eq!(percentile(&data, 0.995), 100);
+        assert_eq!(percentile(&data, 1.0), 100);
+    }
+
+    #[test]
+    fn percentiles_of_large_values_are_close() {
+        let mut data = Histogram::default();
+        for step in 0..1000 {
+            data.record(100_000 + step * 7);
+        }
+        let median = percentile(&data, 0.5) as f64;
+        assert!((median - 103_500.0).abs() / 103_500.0 < 0.001);
+    }
+
+    #[test]
+    fn percentile_error_shrinks_with_more_data() {
+        let mut data = Histogram::default();
+        for value in 1..=100 {
+            data.record(value);
+        }
+        let small = percentile_std_error(&data, 0.5);
+        assert!(small > 0.0);
+        for value in 1..=100 {
+            data.add_count(value, 99);
+        }
+        assert!(percentile_std_error(&data, 0.5) < small);
+        assert_eq!(percentile_std_error(&Histogram::default(), 0.5), 0.0);
+    }
+
+    proptest! {
+        #[test]
+        fn percentiles_are_monotone(data in any_histogram(), pcts in sorted_pcts()) {
+            let results = percentiles(&data, &pcts);
+            for pair in results.windows(2) {
+                prop_assert!(pair[0] <= pair[1], "{:?} for {:?}", results, pcts);
+            }
+        }
+
+        #[test]
+        fn percentiles_are_values_in_the_data(data in any_histogram(), pcts in sorted_pcts()) {
+            for value in percentiles(&data, &pcts) {
+                prop_assert!(data.count(value) > 0, "{} is not in the data", value);
+            }
+        }
+
+        #[test]
+        fn full_percentile_is_the_maximum(data in any_histogram()) {
+            let max = data.max_value().unwrap();
+            prop_assert_eq!(percentile(&data, 1.0), max);
+        }
+
+        #[test]
+        fn mean_is_within_range(data in any_histogram()) {
+            let mean = mean(&data);
+            prop_assert!(mean >= percentile(&data, 0.0) as f64);
+            prop_assert!(mean <= percentile(&data, 1.0) as f64);
+        }
+    }
 }
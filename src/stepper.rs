@@ -0,0 +1,62 @@
+use seed::prelude::*;
+
+use std::ops::RangeInclusive;
+
+use crate::{i18n::tr_args, Msg};
+
+/// Wraps a number input with buttons that step its value down and up by one,
+/// which are much easier to hit on a touch screen than the input's own
+/// arrows. The buttons keep the value within `range`, and `change` makes the
+/// message that sets it. `name` describes the value for screen readers.
+pub fn stepper(
+    input: Node<Msg>,
+    value: i32,
+    range: RangeInclusive<i32>,
+    change: impl Fn(i32) -> Msg,
+    name: &str,
+) -> Node<Msg> {
+    let (down, up) = steps(value, &range);
+    let button = |step: Option<i32>, text: &'static str, label: String| {
+        button![
+            class!["stepper_button"],
+            attrs![
+                At::Type => "button";
+                At::Disabled => step.is_none().as_at_value();
+                "aria-label" => label;
+            ],
+            step.map(|step| simple_ev(Ev::Click, change(step)))
+                .into_iter()
+                .collect::<Vec<_>>(),
+            text,
+        ]
+    };
+    span![
+        class!["stepper"],
+        button(down, "−", tr_args("Decrease {name}", &[("name", &name)])),
+        input,
+        button(up, "+", tr_args("Increase {name}", &[("name", &name)])),
+    ]
+}
+
+/// The values one step down and up from `value`, if they are in `range`. A
+/// value below the range, such as an empty input, steps up to the start of
+/// the range.
+fn steps(value: i32, range: &RangeInclusive<i32>) -> (Option<i32>, Option<i32>) {
+    let down = Some(value - 1).filter(|down| range.contains(down));
+    let up = Some((value + 1).max(*range.start())).filter(|up| range.contains(up));
+    (down, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_stay_in_range() {
+        assert_eq!(steps(3, &(0..=10)), (Some(2), Some(4)));
+        assert_eq!(steps(0, &(0..=10)), (None, Some(1)));
+        assert_eq!(steps(10, &(0..=10)), (Some(9), None));
+        assert_eq!(steps(-1, &(0..=10)), (None, Some(0)));
+        assert_eq!(steps(1, &(1..=1)), (None, None));
+    }
+}
@@ -0,0 +1,46 @@
+use wasm_bindgen::JsCast;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Why a value couldn't be saved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// The browser doesn't allow local storage, such as in some private
+    /// browsing modes.
+    Unavailable,
+    /// The value couldn't be encoded.
+    Encode,
+    /// The site has used up all of the space that the browser allows it.
+    QuotaExceeded,
+    /// The browser refused to save the value for some other reason.
+    Rejected,
+}
+
+/// Saves a value to the browser's local storage under the given key, using the
+/// same representation as the query strings.
+pub fn store<T: Serialize>(key: &str, value: &T) -> Result<(), StoreError> {
+    let storage = seed::storage::get_storage().ok_or(StoreError::Unavailable)?;
+    let data = bincode::serialize(value).map_err(|_| StoreError::Encode)?;
+    storage
+        .set_item(key, &base64::encode(&data))
+        .map_err(|error| {
+            // Firefox used its own name for this error before it followed the
+            // standard.
+            let name = error.dyn_ref::<web_sys::DomException>().map(|e| e.name());
+            match name.as_deref() {
+                Some("QuotaExceededError") | Some("NS_ERROR_DOM_QUOTA_REACHED") => {
+                    StoreError::QuotaExceeded
+                }
+                _ => StoreError::Rejected,
+            }
+        })
+}
+
+/// Loads a value saved with `store`. Returns `None` if there is no saved value
+/// or if it can't be read, such as when it was saved by an older version.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let storage = seed::storage::get_storage()?;
+    let encoded = storage.get_item(key).ok()??;
+    let data = base64::decode(&encoded).ok()?;
+    bincode::deserialize(&data).ok()
+}
@@ -1,31 +1,40 @@
+use crate::i18n::{self, tr, Language};
 use crate::Msg;
 use seed::prelude::*;
 
 /// The header of one of the informational pages.
-fn header() -> Node<Msg> {
+pub fn header() -> Node<Msg> {
     header![
         style![
             "text-align" => "start";
         ],
         a![
-            "Back",
+            tr("Back"),
             attrs! [
                 At::Href => "/";
             ]
         ],
+        " | ",
+        i18n::language_select(),
     ]
 }
 
 /// Page contents for the help page.
 pub fn help() -> Vec<Node<Msg>> {
     let mut els = vec![header()];
-    els.extend(El::from_markdown(include_str!("subpages/help.md")));
+    els.extend(El::from_markdown(match i18n::language() {
+        Language::English => include_str!("subpages/help.md"),
+        Language::Japanese => include_str!("subpages/help.ja.md"),
+    }));
     els
 }
 
 /// Page contents for the changelog page.
 pub fn changelog() -> Vec<Node<Msg>> {
     let mut els = vec![header()];
-    els.extend(El::from_markdown(include_str!("subpages/changelog.md")));
+    els.extend(El::from_markdown(match i18n::language() {
+        Language::English => include_str!("subpages/changelog.md"),
+        Language::Japanese => include_str!("subpages/changelog.ja.md"),
+    }));
     els
 }
@@ -0,0 +1,697 @@
+use seed::prelude::*;
+
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+use strum::IntoEnumIterator;
+
+use crate::{
+    banner::{Banner, STARTING_RATES},
+    batch::BatchRun,
+    goal::{Goal, GoalPreset},
+    histogram::Histogram,
+    i18n::{tr, tr_args},
+    stats, subpages, Color, Msg,
+};
+
+/// Percentiles that can be shown for each cell of a sweep.
+pub const PERCENTILE_OPTIONS: [u8; 7] = [10, 25, 50, 75, 90, 95, 99];
+
+/// The banners and goals to run in a sweep, and what to report about them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepSettings {
+    /// The goals to run, each for `copies` copies.
+    pub presets: Vec<GoalPreset>,
+    pub copies: u8,
+    /// The smallest and largest focus sizes to run.
+    pub min_size: i8,
+    pub max_size: i8,
+    /// The color whose number of focus units changes, or `None` for all of
+    /// them at once. Other colors keep their sizes from the current banner.
+    pub size_color: Option<Color>,
+    pub rates: Vec<(u8, u8)>,
+    /// Which focus charge settings to run.
+    pub focus_charges: Vec<bool>,
+    /// Percentiles to report, in ascending order.
+    pub percentiles: Vec<u8>,
+    /// Number of simulations to run for each cell.
+    pub trials: u32,
+}
+
+impl Default for SweepSettings {
+    fn default() -> Self {
+        SweepSettings {
+            presets: vec![GoalPreset::RedFocus],
+            copies: 1,
+            min_size: 1,
+            max_size: 4,
+            size_color: None,
+            rates: vec![(3, 3), (5, 3), (4, 2)],
+            focus_charges: vec![false, true],
+            percentiles: vec![50, 90],
+            trials: 10_000,
+        }
+    }
+}
+
+/// One banner and goal in a sweep.
+#[derive(Copy, Clone, Debug)]
+pub struct SweepCell {
+    pub preset: GoalPreset,
+    pub banner: Banner,
+    /// The focus size that the sweep set for this cell.
+    pub size: i8,
+}
+
+/// The results for one cell of a sweep.
+#[derive(Clone, Debug)]
+pub struct SweepRow {
+    pub cell: SweepCell,
+    pub mean: f64,
+    /// Orbs spent at each of the sweep's percentiles.
+    pub percentiles: Vec<u32>,
+}
+
+impl SweepSettings {
+    pub fn sizes(&self) -> RangeInclusive<i8> {
+        self.min_size.max(0)..=self.max_size
+    }
+
+    pub fn goal(&self, preset: GoalPreset) -> Goal {
+        Goal::Preset(preset, self.copies)
+    }
+
+    /// Every banner and goal in the sweep, based on `base`, leaving out goals
+    /// that aren't available on their banner. Cells are ordered by goal, rates,
+    /// focus charges, and then focus size, so that each row of the results
+    /// table is together.
+    pub fn cells(&self, base: &Banner) -> Vec<SweepCell> {
+        let mut cells = vec![];
+        for &preset in &self.presets {
+            for &rates in &self.rates {
+                for &focus_charges in &self.focus_charges {
+                    for size in self.sizes() {
+                        let mut banner = Banner {
+                            starting_rates: rates,
+                            focus_charges,
+                            // 4* focus units only appear on normal banners.
+                            fourstar_focus: base.fourstar_focus.filter(|_| rates == (3, 3)),
+                            ..*base
+                        };
+                        match self.size_color {
+                            Some(color) => banner.focus_sizes[color as usize] = size,
+                            None => banner.focus_sizes = [size; 4],
+                        }
+                        if preset.is_available(&banner) {
+                            cells.push(SweepCell {
+                                preset,
+                                banner,
+                                size,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// Summarizes the results of simulating a cell.
+    pub fn row(&self, cell: SweepCell, data: &Histogram) -> SweepRow {
+        let pcts: Vec<f32> = self
+            .percentiles
+            .iter()
+            .map(|&pct| pct as f32 / 100.0)
+            .collect();
+        SweepRow {
+            cell,
+            mean: stats::mean(data),
+            percentiles: stats::percentiles(data, &pcts),
+        }
+    }
+
+    /// Problems that stop the sweep from being run.
+    pub fn problem(&self, base: &Banner) -> Option<&'static str> {
+        if self.percentiles.is_empty() {
+            Some(tr("Choose at least one percentile."))
+        } else if self.trials == 0 {
+            Some(tr("Choose a number of simulations."))
+        } else if self.cells(base).is_empty() {
+            Some(tr("None of the goals are available on these banners."))
+        } else {
+            None
+        }
+    }
+}
+
+/// Adds the item to the list if it isn't there, or removes it if it is,
+/// keeping the list in the same order as `order`.
+pub fn toggle<T: Copy + PartialEq>(list: &mut Vec<T>, item: T, order: impl IntoIterator<Item = T>) {
+    let present = list.contains(&item);
+    *list = order
+        .into_iter()
+        .filter(|&option| {
+            if option == item {
+                !present
+            } else {
+                list.contains(&option)
+            }
+        })
+        .collect();
+}
+
+/// Runs the whole sweep at once on several threads, for native builds. The
+/// same seed always gives the same results.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(settings: &SweepSettings, base: &Banner, seed: u64, threads: usize) -> Vec<SweepRow> {
+    settings
+        .cells(base)
+        .into_iter()
+        .enumerate()
+        .map(|(index, cell)| {
+            let data = crate::parallel::run(
+                cell.banner,
+                &settings.goal(cell.preset),
+                settings.trials as u64,
+                seed.wrapping_add(index as u64),
+                threads,
+            );
+            settings.row(cell, &data)
+        })
+        .collect()
+}
+
+/// The state of the sweep page.
+#[derive(Default, Debug)]
+pub struct SweepState {
+    /// The settings being edited.
+    pub settings: SweepSettings,
+    /// The settings that the current results are for.
+    pub run_settings: SweepSettings,
+    /// The cells of the current run, including ones that aren't done yet.
+    pub cells: Vec<SweepCell>,
+    pub run: BatchRun,
+    pub results: Vec<SweepRow>,
+    /// Index of the percentile shown in the table.
+    pub shown_percentile: usize,
+}
+
+impl SweepState {
+    /// Starts a new run of the current settings.
+    pub fn start(&mut self, base: &Banner) {
+        self.run_settings = self.settings.clone();
+        self.cells = self.settings.cells(base);
+        let jobs = self
+            .cells
+            .iter()
+            .map(|cell| (cell.banner, self.settings.goal(cell.preset)))
+            .collect();
+        self.run = BatchRun::new(jobs, self.settings.trials);
+        self.results.clear();
+        self.shown_percentile = self
+            .shown_percentile
+            .min(self.settings.percentiles.len() - 1);
+    }
+
+    /// Runs simulations for a short time, returning whether there are more
+    /// to run.
+    pub fn step(&mut self) -> bool {
+        let more = self.run.step();
+        for (cell, data) in self
+            .cells
+            .iter()
+            .zip(&self.run.results)
+            .skip(self.results.len())
+        {
+            self.results.push(self.run_settings.row(*cell, data));
+        }
+        more
+    }
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The results as a CSV file with one line for each cell.
+pub fn to_csv(settings: &SweepSettings, results: &[SweepRow]) -> String {
+    let mut csv = String::from(
+        "goal,copies,focus_rate,fivestar_rate,focus_charges,\
+         red_focus,blue_focus,green_focus,colorless_focus,fourstar_focus,mean",
+    );
+    for pct in &settings.percentiles {
+        csv += &format!(",p{}", pct);
+    }
+    csv += "\n";
+    for row in results {
+        let banner = &row.cell.banner;
+        let [red, blue, green, colorless] = banner.focus_sizes;
+        csv += &format!(
+            "{},{},{},{},{},{},{},{},{},{},{:.2}",
+            csv_field(row.cell.preset.name()),
+            settings.copies,
+            banner.starting_rates.0,
+            banner.starting_rates.1,
+            banner.focus_charges,
+            red,
+            blue,
+            green,
+            colorless,
+            banner.fourstar_focus.map_or("", Color::name),
+            row.mean,
+        );
+        for value in &row.percentiles {
+            csv += &format!(",{}", value);
+        }
+        csv += "\n";
+    }
+    csv
+}
+
+/// Description of a banner's starting rates.
+fn rates_label(rates: (u8, u8)) -> &'static str {
+    STARTING_RATES
+        .iter()
+        .find(|&&(option, _)| option == rates)
+        .map_or("", |&(_, label)| tr(label))
+}
+
+/// A checkbox with a label.
+fn checkbox(id: String, checked: bool, label: &str, msg: Msg) -> Node<Msg> {
+    span![
+        class!["padleft"],
+        input![
+            id![&id],
+            simple_ev(Ev::Input, msg),
+            attrs![At::Type => "checkbox"; At::Checked => checked.as_at_value()],
+        ],
+        label![attrs![At::For => id], label],
+    ]
+}
+
+/// Inputs for the sweep settings.
+fn settings_form(settings: &SweepSettings, base: &Banner, running: bool) -> Node<Msg> {
+    let min_size = settings.min_size;
+    let max_size = settings.max_size;
+    let problem = settings.problem(base);
+    div![
+        id!["sweep_settings"],
+        div![
+            tr("Goals:"),
+            GoalPreset::iter()
+                .map(|preset| checkbox(
+                    format!("sweep_preset_{:?}", preset),
+                    settings.presets.contains(&preset),
+                    &preset.to_string(),
+                    Msg::SweepPresetToggle { preset },
+                ))
+                .collect::<Vec<_>>(),
+        ],
+        div![
+            label![attrs![At::For => "sweep_copies"], tr("Copies of each: ")],
+            input![
+                id!["sweep_copies"],
+                input_ev(Ev::Input, |text| match text.parse::<u8>() {
+                    Ok(copies) if copies > 0 => Msg::SweepCopiesChange { copies },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => settings.copies;
+                    At::Min => 1;
+                ],
+            ],
+        ],
+        div![
+            label![attrs![At::For => "sweep_min_size"], tr("Focus sizes from ")],
+            input![
+                id!["sweep_min_size"],
+                input_ev(Ev::Input, move |text| match text.parse::<i8>() {
+                    Ok(min) => Msg::SweepSizeRangeChange { min, max: max_size },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => min_size;
+                    At::Min => 0;
+                ],
+            ],
+            label![attrs![At::For => "sweep_max_size"], tr(" to ")],
+            input![
+                id!["sweep_max_size"],
+                input_ev(Ev::Input, move |text| match text.parse::<i8>() {
+                    Ok(max) => Msg::SweepSizeRangeChange { min: min_size, max },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => max_size;
+                    At::Min => 0;
+                ],
+            ],
+            label![attrs![At::For => "sweep_size_color"], tr(" for ")],
+            select![
+                id!["sweep_size_color"],
+                input_ev(Ev::Input, |text| Msg::SweepSizeColorChange {
+                    color: text
+                        .parse::<u8>()
+                        .ok()
+                        .and_then(|color| Color::try_from(color).ok()),
+                }),
+                option![
+                    attrs![At::Value => "All"; At::Selected => settings.size_color.is_none().as_at_value()],
+                    tr("every color")
+                ],
+                Color::iter()
+                    .map(|color| option![
+                        attrs![
+                            At::Value => color as usize;
+                            At::Selected => (settings.size_color == Some(color)).as_at_value();
+                        ],
+                        color.to_string()
+                    ])
+                    .collect::<Vec<_>>(),
+            ],
+        ],
+        div![
+            tr("Rates:"),
+            STARTING_RATES
+                .iter()
+                .map(|&(rates, label)| checkbox(
+                    format!("sweep_rates_{}_{}", rates.0, rates.1),
+                    settings.rates.contains(&rates),
+                    tr(label),
+                    Msg::SweepRatesToggle { rates },
+                ))
+                .collect::<Vec<_>>(),
+        ],
+        div![
+            tr("Focus charges:"),
+            checkbox(
+                "sweep_charges_off".into(),
+                settings.focus_charges.contains(&false),
+                tr("Off"),
+                Msg::SweepFocusChargesToggle {
+                    focus_charges: false
+                },
+            ),
+            checkbox(
+                "sweep_charges_on".into(),
+                settings.focus_charges.contains(&true),
+                tr("On"),
+                Msg::SweepFocusChargesToggle {
+                    focus_charges: true
+                },
+            ),
+        ],
+        div![
+            tr("Percentiles:"),
+            PERCENTILE_OPTIONS
+                .iter()
+                .map(|&percentile| checkbox(
+                    format!("sweep_percentile_{}", percentile),
+                    settings.percentiles.contains(&percentile),
+                    &format!("{}%", percentile),
+                    Msg::SweepPercentileToggle { percentile },
+                ))
+                .collect::<Vec<_>>(),
+        ],
+        div![
+            label![
+                attrs![At::For => "sweep_trials"],
+                tr("Simulations per cell: ")
+            ],
+            input![
+                id!["sweep_trials"],
+                input_ev(Ev::Input, |text| match text.parse::<u32>() {
+                    Ok(trials) => Msg::SweepTrialsChange { trials },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Value => settings.trials;
+                    At::Min => 1;
+                    At::Step => 1000;
+                ],
+            ],
+        ],
+        div![
+            if running {
+                button![simple_ev(Ev::Click, Msg::SweepStop), tr("Stop")]
+            } else {
+                button![
+                    simple_ev(Ev::Click, Msg::SweepStart),
+                    if problem.is_some() {
+                        attrs![At::Disabled => true]
+                    } else {
+                        attrs![]
+                    },
+                    tr_args(
+                        "Run {count} cells",
+                        &[("count", &settings.cells(base).len())]
+                    ),
+                ]
+            },
+            span![class!["goal_error"], " ", problem.unwrap_or("")],
+        ],
+    ]
+}
+
+/// Hue of the background color for a value, going from green for the lowest
+/// value in the table to red for the highest. The saturation and lightness
+/// come from the theme.
+fn heat_hue(value: u32, range: (u32, u32)) -> String {
+    let (low, high) = range;
+    let fraction = if high > low {
+        (value - low) as f32 / (high - low) as f32
+    } else {
+        0.0
+    };
+    format!("{:.0}", 120.0 * (1.0 - fraction))
+}
+
+/// The results of the sweep as a heatmap, with a row for each goal, rate,
+/// and focus charge setting, and a column for each focus size.
+fn results_table(state: &SweepState) -> Node<Msg> {
+    let settings = &state.run_settings;
+    let shown = state.shown_percentile;
+    let values = state.results.iter().map(|row| row.percentiles[shown]);
+    let range = (values.clone().min().unwrap_or(0), values.max().unwrap_or(0));
+
+    let mut body = tbody![];
+    let mut rows = state.results.iter().peekable();
+    while let Some(first) = rows.next() {
+        let key = |row: &SweepRow| {
+            (
+                row.cell.preset,
+                row.cell.banner.starting_rates,
+                row.cell.banner.focus_charges,
+            )
+        };
+        let mut group = vec![first];
+        while let Some(row) = rows.next_if(|row| key(row) == key(first)) {
+            group.push(row);
+        }
+        let mut tr = tr![
+            td![first.cell.preset.to_string()],
+            td![rates_label(first.cell.banner.starting_rates)],
+            td![tr(if first.cell.banner.focus_charges {
+                "On"
+            } else {
+                "Off"
+            })],
+        ];
+        for size in settings.sizes() {
+            tr.add_child(match group.iter().find(|row| row.cell.size == size) {
+                Some(row) => {
+                    let value = row.percentiles[shown];
+                    td![
+                        class!["sweep_value"],
+                        style!["--heat-hue" => heat_hue(value, range)],
+                        attrs![At::Title => tr_args("Mean: {mean}", &[("mean", &format!("{:.1}", row.mean))])],
+                        value.to_string(),
+                    ]
+                }
+                None => td![],
+            });
+        }
+        body.add_child(tr);
+    }
+
+    div![
+        div![
+            label![attrs![At::For => "sweep_shown_percentile"], tr("Show: ")],
+            select![
+                id!["sweep_shown_percentile"],
+                input_ev(Ev::Input, |text| match text.parse::<usize>() {
+                    Ok(index) => Msg::SweepShownPercentileChange { index },
+                    _ => Msg::Null,
+                }),
+                settings
+                    .percentiles
+                    .iter()
+                    .enumerate()
+                    .map(|(index, pct)| option![
+                        attrs![
+                            At::Value => index;
+                            At::Selected => (index == shown).as_at_value();
+                        ],
+                        tr_args("{pct}th percentile", &[("pct", pct)]),
+                    ])
+                    .collect::<Vec<_>>(),
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::SweepExport),
+                tr("Export CSV")
+            ],
+            if state.run.is_running() {
+                span![
+                    class!["padleft"],
+                    tr_args(
+                        "{done} of {count} cells done",
+                        &[("done", &state.results.len()), ("count", &state.run.len())],
+                    )
+                ]
+            } else {
+                empty![]
+            },
+        ],
+        table![
+            id!["sweep_results"],
+            thead![tr![
+                th![tr("Goal")],
+                th![tr("Rates")],
+                th![tr("Focus charges")],
+                settings
+                    .sizes()
+                    .map(|size| th![match settings.size_color {
+                        Some(color) => format!("{} {}", size, color),
+                        None => tr_args("{size} each", &[("size", &size)]),
+                    }])
+                    .collect::<Vec<_>>(),
+            ]],
+            body,
+        ],
+    ]
+}
+
+/// Page contents for running a sweep over banner settings and goals. `base`
+/// is the current banner, which the sweep's banners start from.
+pub fn sweep_page(state: &SweepState, base: &Banner) -> Vec<Node<Msg>> {
+    vec![
+        subpages::header(),
+        h2![tr("Sweep")],
+        p![tr_args(
+            "Orbs needed for each goal over a range of banners. Banners start from the current one: {banner}",
+            &[("banner", base)],
+        )],
+        settings_form(&state.settings, base, state.run.is_running()),
+        if state.results.is_empty() {
+            empty![]
+        } else {
+            results_table(state)
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::i18n::{self, Language};
+
+    #[test]
+    fn cells_cover_the_grid_in_row_order() {
+        let settings = SweepSettings {
+            presets: vec![GoalPreset::RedFocus, GoalPreset::BlueFocus],
+            size_color: Some(Color::Red),
+            min_size: 0,
+            max_size: 2,
+            rates: vec![(3, 3), (5, 3)],
+            focus_charges: vec![false, true],
+            ..SweepSettings::default()
+        };
+        let cells = settings.cells(&Banner::default());
+        // The red focus goal isn't available without red focus units.
+        assert_eq!(cells.len(), 2 * 2 * 2 + 2 * 2 * 3);
+        assert!(cells[..8]
+            .iter()
+            .all(|cell| cell.preset == GoalPreset::RedFocus && cell.size > 0));
+        assert_eq!(
+            cells[..2].iter().map(|cell| cell.size).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        for cell in &cells {
+            assert_eq!(cell.banner.focus_sizes[Color::Red as usize], cell.size);
+            assert_eq!(cell.banner.focus_sizes[Color::Blue as usize], 1);
+        }
+    }
+
+    #[test]
+    fn fourstar_focus_only_on_normal_rates() {
+        let base = Banner {
+            fourstar_focus: Some(Color::Green),
+            ..Banner::default()
+        };
+        for cell in SweepSettings::default().cells(&base) {
+            assert_eq!(
+                cell.banner.fourstar_focus.is_some(),
+                cell.banner.starting_rates == (3, 3)
+            );
+        }
+    }
+
+    #[test]
+    fn toggle_keeps_option_order() {
+        let mut list = vec![50, 90];
+        toggle(&mut list, 10, PERCENTILE_OPTIONS.iter().copied());
+        assert_eq!(list, vec![10, 50, 90]);
+        toggle(&mut list, 50, PERCENTILE_OPTIONS.iter().copied());
+        assert_eq!(list, vec![10, 90]);
+    }
+
+    #[test]
+    fn csv_has_a_line_per_row() {
+        let settings = SweepSettings::default();
+        let cell = settings.cells(&Banner::default())[0];
+        let mut data = Histogram::default();
+        data.record(10);
+        data.record(20);
+        let csv = to_csv(&settings, &[settings.row(cell, &data)]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(",mean,p50,p90"));
+        assert_eq!(
+            lines[1],
+            "Specific red 5* focus unit,1,3,3,false,1,1,1,1,,15.00,20,20"
+        );
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+    }
+
+    #[test]
+    fn csv_does_not_depend_on_the_language() {
+        let settings = SweepSettings::default();
+        let base = Banner {
+            fourstar_focus: Some(Color::Green),
+            ..Banner::default()
+        };
+        let rows: Vec<SweepRow> = settings
+            .cells(&base)
+            .into_iter()
+            .map(|cell| settings.row(cell, &Histogram::default()))
+            .collect();
+        let english = to_csv(&settings, &rows);
+        i18n::set_language(Language::Japanese);
+        assert_eq!(to_csv(&settings, &rows), english);
+        assert!(english.contains(",Green,"));
+    }
+}
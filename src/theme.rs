@@ -0,0 +1,136 @@
+//! Color themes for the page and the graph.
+//!
+//! The colors themselves are CSS variables in `style.css`, chosen by the
+//! `data-theme` attribute on the root element, so switching themes doesn't
+//! need the page to be rendered again.
+
+use seed::prelude::*;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{i18n::tr, storage, Msg};
+
+/// Local storage key for the chosen theme.
+const THEME_KEY: &str = "feh-sim-theme";
+
+/// A set of colors for the page and the graph.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum Theme {
+    /// Light or dark, whichever the browser prefers.
+    #[default]
+    System,
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Theme::*;
+        let s = match *self {
+            System => "Follow system",
+            Light => "Light",
+            Dark => "Dark",
+            HighContrast => "High contrast",
+        };
+        f.write_str(tr(s))
+    }
+}
+
+impl Theme {
+    /// The value of the `data-theme` attribute that selects the theme's
+    /// colors in the stylesheet.
+    pub fn code(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high_contrast",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Theme::iter().find(|theme| theme.code() == code)
+    }
+
+    /// The theme that is actually shown, replacing `System` with the theme
+    /// that the browser prefers.
+    pub fn resolve(self) -> Theme {
+        if self != Theme::System {
+            return self;
+        }
+        let prefers_dark = seed::window()
+            .match_media("(prefers-color-scheme: dark)")
+            .ok()
+            .flatten()
+            .is_some_and(|query| query.matches());
+        if prefers_dark {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
+
+/// Shows the page in the given theme.
+pub fn apply_theme(theme: Theme) {
+    if let Some(root) = seed::document().document_element() {
+        let _ = root.set_attribute("data-theme", theme.code());
+    }
+}
+
+/// Saves the chosen theme so that it is used the next time the app is opened.
+/// The app works fine with the default theme if it can't be saved.
+pub fn save_theme(theme: Theme) {
+    let _ = storage::store(THEME_KEY, &theme);
+}
+
+/// The theme that was chosen the last time the app was used, if any.
+pub fn load_theme() -> Option<Theme> {
+    storage::load(THEME_KEY)
+}
+
+/// Selector for the color theme.
+pub fn theme_select(current: Theme) -> Node<Msg> {
+    select![
+        id!["theme"],
+        attrs!["aria-label" => tr("Theme")],
+        input_ev(Ev::Input, |code| match Theme::from_code(&code) {
+            Some(theme) => Msg::ThemeChange { theme },
+            None => Msg::Null,
+        }),
+        Theme::iter()
+            .map(|theme| option![
+                attrs![
+                    At::Value => theme.code();
+                    At::Selected => (theme == current).as_at_value();
+                ],
+                theme.to_string()
+            ])
+            .collect::<Vec<_>>(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        for theme in Theme::iter() {
+            assert_eq!(Theme::from_code(theme.code()), Some(theme));
+        }
+        assert_eq!(Theme::from_code("sepia"), None);
+    }
+
+    #[test]
+    fn only_system_needs_resolving() {
+        for theme in Theme::iter().filter(|&theme| theme != Theme::System) {
+            assert_eq!(theme.resolve(), theme);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{banner::Banner, goal::Goal, histogram::Histogram, precision::Precision};
+
+/// Maximum number of snapshots kept in each direction.
+const MAX_UNDO: usize = 50;
+
+/// The state that is restored by undoing or redoing a change.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub banner: Banner,
+    pub goal: Goal,
+    pub data: Histogram,
+    /// The history entry that `data` belongs to.
+    pub current_run: Option<f64>,
+    /// How precise `data` was, if it came from a run to precision.
+    pub precision: Option<Precision>,
+}
+
+/// Past and undone states of the banner, goal, and results.
+#[derive(Default, Debug)]
+pub struct UndoStack {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl UndoStack {
+    /// Records the state from before a change, discarding anything that was
+    /// undone since it can no longer be redone.
+    pub fn record(&mut self, snapshot: Snapshot) {
+        push_limited(&mut self.undo, snapshot);
+        self.redo.clear();
+    }
+
+    /// Returns the state to go back to, remembering `current` so that it can be
+    /// redone.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        push_limited(&mut self.redo, current);
+        Some(previous)
+    }
+
+    /// Returns the state that was most recently undone, remembering `current`
+    /// so that it can be undone again.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        push_limited(&mut self.undo, current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Pushes onto the stack, dropping the oldest entry if it is full.
+fn push_limited(stack: &mut Vec<Snapshot>, snapshot: Snapshot) {
+    if stack.len() >= MAX_UNDO {
+        stack.remove(0);
+    }
+    stack.push(snapshot);
+}
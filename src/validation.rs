@@ -0,0 +1,208 @@
+use seed::prelude::*;
+
+use std::fmt;
+
+use crate::{
+    banner::Banner,
+    goal::{Goal, GoalExpr, GoalKind},
+    i18n::{tr, tr_args},
+    Color, Msg, Pool,
+};
+
+/// How serious a problem with the goal is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The goal can't be simulated as written.
+    Error,
+    /// The goal can be simulated, but part of it is ignored.
+    Warning,
+}
+
+/// A problem found with a goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Path of child indexes to the part of the goal with the problem, in the
+    /// same form as for nested goals. The parts of a flat custom goal are
+    /// children of the outermost group.
+    pub path: Vec<usize>,
+    pub kind: IssueKind,
+}
+
+/// The kinds of problems that a goal can have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The preset has no units on the banner.
+    PresetUnavailable,
+    /// The unit isn't in its pool on the banner.
+    ImpossiblePart { pool: Pool, color: Color, unit: u8 },
+    /// The banner has no 4* focus unit of the part's color.
+    NoFourstarFocus { color: Color },
+    /// The banner has no general 5* pool.
+    NoGeneralFivestars,
+    /// The part asks for zero copies.
+    NoCopies,
+    /// The part targets the same unit as an earlier part in the same group.
+    DuplicateUnit,
+    /// The group needs more of its children than can be completed.
+    ThresholdTooHigh { needed: usize, possible: usize },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IssueKind::*;
+        let text = match *self {
+            PresetUnavailable => tr("This goal has no units on the banner.").to_string(),
+            ImpossiblePart { pool, color, unit } => {
+                if pool == Pool::Focus && unit == 0 {
+                    tr_args(
+                        "This banner has no {color} focus units.",
+                        &[("color", &color)],
+                    )
+                } else {
+                    tr_args(
+                        "This banner doesn't have {count} {color} {pool} units.",
+                        &[("count", &(unit + 1)), ("color", &color), ("pool", &pool)],
+                    )
+                }
+            }
+            NoFourstarFocus { color } => tr_args(
+                "This banner has no {color} 4* focus unit.",
+                &[("color", &color)],
+            ),
+            NoGeneralFivestars => tr("This banner has no general 5* units.").to_string(),
+            NoCopies => tr("No copies are needed of this unit.").to_string(),
+            DuplicateUnit => tr("This is the same unit as another one in this group.").to_string(),
+            ThresholdTooHigh { needed, possible } => tr_args(
+                "This needs {needed} of these to be completed, but only {possible} can be.",
+                &[("needed", &needed), ("possible", &possible)],
+            ),
+        };
+        f.write_str(&text)
+    }
+}
+
+/// Checks the goal for problems on the given banner.
+pub fn validate(goal: &Goal, banner: &Banner) -> Vec<Issue> {
+    let mut issues = vec![];
+    match goal {
+        Goal::Preset(preset, _) => {
+            if !preset.is_available(banner) {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    path: vec![],
+                    kind: IssueKind::PresetUnavailable,
+                });
+            }
+        }
+        Goal::Custom(_) | Goal::Expr(_) => {
+            let expr = goal.as_expr(banner);
+            validate_expr(&expr, banner, vec![], false, &mut issues);
+            // A group that can't be completed only stops the goal from being
+            // simulated if the goal as a whole can't be completed without it.
+            if expr.can_complete(banner, false) {
+                for issue in &mut issues {
+                    if let IssueKind::ThresholdTooHigh { .. } = issue.kind {
+                        issue.severity = Severity::Warning;
+                    }
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Checks a part of a goal, adding its problems to `issues`. `in_total` says
+/// whether the expression is directly inside a `GoalKind::Total` group.
+fn validate_expr(
+    expr: &GoalExpr,
+    banner: &Banner,
+    path: Vec<usize>,
+    in_total: bool,
+    issues: &mut Vec<Issue>,
+) {
+    let warning = |path: Vec<usize>, kind: IssueKind| Issue {
+        severity: Severity::Warning,
+        path,
+        kind,
+    };
+    match expr {
+        GoalExpr::Part { part, unit } => {
+            let color = part.unit_color;
+            if part.num_copies == 0 && !in_total {
+                issues.push(warning(path.clone(), IssueKind::NoCopies));
+            }
+            match part.pool {
+                Pool::FourstarFocus if banner.fourstar_focus != Some(color) => {
+                    issues.push(warning(path, IssueKind::NoFourstarFocus { color }))
+                }
+                Pool::Fivestar if !banner.has_general_fivestars() => {
+                    issues.push(warning(path, IssueKind::NoGeneralFivestars))
+                }
+                pool => {
+                    if !expr.can_complete(banner, true) {
+                        let unit = if pool == Pool::FourstarFocus {
+                            0
+                        } else {
+                            *unit
+                        };
+                        issues.push(warning(
+                            path,
+                            IssueKind::ImpossiblePart { pool, color, unit },
+                        ));
+                    }
+                }
+            }
+        }
+        GoalExpr::Group { kind, children } => {
+            let child_in_total = matches!(kind, GoalKind::Total(_));
+            let mut seen = vec![];
+            for (index, child) in children.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                if let GoalExpr::Part { part, unit } = child {
+                    let target = (part.pool, part.unit_color, *unit);
+                    if seen.contains(&target) {
+                        issues.push(warning(child_path.clone(), IssueKind::DuplicateUnit));
+                    }
+                    seen.push(target);
+                }
+                validate_expr(child, banner, child_path, child_in_total, issues);
+            }
+
+            let possible = children
+                .iter()
+                .filter(|child| child.can_complete(banner, child_in_total))
+                .count();
+            let needed = kind.children_needed(children.len());
+            if needed > possible {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    path,
+                    kind: IssueKind::ThresholdTooHigh { needed, possible },
+                });
+            }
+        }
+    }
+}
+
+/// Whether any of the issues stop the goal from being simulated.
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|issue| issue.severity == Severity::Error)
+}
+
+/// Messages for the issues with the part of the goal at the given path.
+pub fn issue_messages(issues: &[Issue], path: &[usize]) -> Node<Msg> {
+    let mut messages = span![];
+    for issue in issues.iter().filter(|issue| issue.path == path) {
+        messages.add_child(span![
+            match issue.severity {
+                Severity::Error => class!["goal_error"],
+                Severity::Warning => class!["goal_warning"],
+            },
+            " ",
+            issue.kind.to_string(),
+        ]);
+    }
+    messages
+}
@@ -1,91 +1,150 @@
 use rand::{distributions::Distribution, Rng};
 
-/// Optimized version of rand::WeightedIndex for a fixed-size collection of four floats.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct WeightedIndex4 {
-    // Cumulative weights stored for faster lookup
-    values: [f32; 4],
+/// The weight of one column of an `AliasTable`.
+const COLUMN: u64 = 1 << 32;
+
+/// Optimized replacement for rand::WeightedIndex over a fixed number of
+/// choices, using the alias method. Each choice takes one random `u64`, no
+/// matter how many choices there are: the high half picks a column, and the
+/// low half picks between the column's own index and its alias.
+#[derive(Copy, Clone, Debug)]
+pub struct AliasTable<const N: usize> {
+    // Chance out of `COLUMN` of each column choosing its own index. Indexes
+    // with no weight have no chance, and are never anyone's alias, so they
+    // are never chosen.
+    thresholds: [u64; N],
+    aliases: [u8; N],
 }
 
-impl WeightedIndex4 {
-    /// Constructs a sampler from the given weights. Weights do not need to sum to 1.
-    pub fn new<T: Into<f32> + Copy>(values: [T; 4]) -> Self {
-        let total = values[0].into() + values[1].into() + values[2].into() + values[3].into();
+impl<const N: usize> Default for AliasTable<N> {
+    fn default() -> Self {
         Self {
-            values: [
-                values[0].into() / total,
-                (values[0].into() + values[1].into()) / total,
-                (values[0].into() + values[1].into() + values[2].into()) / total,
-                1.0,
-            ],
+            thresholds: [0; N],
+            aliases: [0; N],
         }
     }
 }
 
-impl Distribution<usize> for WeightedIndex4 {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
-        let choice = rng.gen::<f32>();
-        if choice > self.values[1] {
-            if choice > self.values[2] {
-                3
-            } else {
-                2
-            }
-        } else {
-            if choice > self.values[0] {
-                1
-            } else {
-                0
+impl<const N: usize> AliasTable<N> {
+    /// Constructs a sampler from the given weights. Weights do not need to sum
+    /// to 1, but at least one must be above 0.
+    pub fn new(weights: [f64; N]) -> Self {
+        let total: f64 = weights.iter().sum();
+
+        // Work with whole numbers that add up to exactly `N` columns, so that
+        // rounding errors can't leave a column unfilled.
+        let mut scaled = [0u64; N];
+        for (scaled, &weight) in scaled.iter_mut().zip(&weights) {
+            *scaled = (weight / total * (N as u64 * COLUMN) as f64) as u64;
+        }
+        let largest = (0..N).max_by_key(|&i| scaled[i]).unwrap();
+        let sum: u64 = scaled.iter().sum();
+        scaled[largest] = scaled[largest] + N as u64 * COLUMN - sum;
+
+        let mut table = Self::default();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..N).partition(|&i| scaled[i] < COLUMN);
+        while let (Some(&under), Some(&over)) = (small.last(), large.last()) {
+            small.pop();
+            table.thresholds[under] = scaled[under];
+            table.aliases[under] = over as u8;
+            scaled[over] -= COLUMN - scaled[under];
+            if scaled[over] < COLUMN {
+                large.pop();
+                small.push(over);
             }
         }
+        // Everything left over fills its column exactly.
+        for i in large {
+            table.thresholds[i] = COLUMN;
+            table.aliases[i] = i as u8;
+        }
+        table
     }
 }
 
-/// Optimized version of rand::WeightedIndex for a fixed-size collection of five floats.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct WeightedIndex5 {
-    // Cumulative weights stored for faster lookup
-    values: [f32; 5],
+impl<const N: usize> Distribution<usize> for AliasTable<N> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let choice = rng.next_u64();
+        let column = (((choice >> 32) * N as u64) >> 32) as usize;
+        if choice & (COLUMN - 1) < self.thresholds[column] {
+            column
+        } else {
+            self.aliases[column] as usize
+        }
+    }
 }
 
-impl WeightedIndex5 {
-    /// Constructs a sampler from the given weights. Weights do not need to sum to 1.
-    pub fn new<T: Into<f32> + Copy>(values: [T; 5]) -> Self {
-        let total = values[0].into()
-            + values[1].into()
-            + values[2].into()
-            + values[3].into()
-            + values[4].into();
-        Self {
-            values: [
-                values[0].into() / total,
-                (values[0].into() + values[1].into()) / total,
-                (values[0].into() + values[1].into() + values[2].into()) / total,
-                (values[0].into() + values[1].into() + values[2].into() + values[3].into()) / total,
-                1.0,
-            ],
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    /// Weights with at least one nonzero entry.
+    fn any_weights(len: usize) -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(0u8..5, len).prop_filter("weights must not all be zero", |weights| {
+            weights.iter().any(|&x| x > 0)
+        })
+    }
+
+    /// Checks that a table made from the weights never chooses an index with
+    /// no weight.
+    fn check_zero_weights<const N: usize>(weights: &[u8], seed: u64) -> Result<(), TestCaseError> {
+        let mut array = [0.0; N];
+        for (weight, &x) in array.iter_mut().zip(weights) {
+            *weight = x as f64;
+        }
+        let dist = AliasTable::new(array);
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for _ in 0..200 {
+            let index = dist.sample(&mut rng);
+            prop_assert!(index < N);
+            prop_assert!(weights[index] > 0, "chose {} from {:?}", index, weights);
         }
+        Ok(())
     }
-}
 
-impl Distribution<usize> for WeightedIndex5 {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
-        let choice = rng.gen::<f32>();
-        if choice > self.values[1] {
-            if choice > self.values[2] {
-                if choice > self.values[3] {
-                    4
-                } else {
-                    3
-                }
-            } else {
-                2
+    proptest! {
+        #[test]
+        fn alias_table_4_never_chooses_zero_weights(
+            weights in any_weights(4),
+            seed in any::<u64>(),
+        ) {
+            check_zero_weights::<4>(&weights, seed)?;
+        }
+
+        #[test]
+        fn alias_table_5_never_chooses_zero_weights(
+            weights in any_weights(5),
+            seed in any::<u64>(),
+        ) {
+            check_zero_weights::<5>(&weights, seed)?;
+        }
+
+        #[test]
+        fn alias_table_20_never_chooses_zero_weights(
+            weights in any_weights(20),
+            seed in any::<u64>(),
+        ) {
+            check_zero_weights::<20>(&weights, seed)?;
+        }
+
+        #[test]
+        fn alias_table_matches_weights(seed in any::<u64>()) {
+            let weights = [1.0, 2.0, 3.0, 4.0, 10.0];
+            let dist = AliasTable::new(weights);
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut counts = [0u32; 5];
+            let samples = 20_000;
+            for _ in 0..samples {
+                counts[dist.sample(&mut rng)] += 1;
             }
-        } else {
-            if choice > self.values[0] {
-                1
-            } else {
-                0
+            for (&count, &weight) in counts.iter().zip(&weights) {
+                let expected = samples as f64 * weight / 20.0;
+                // Well over five standard deviations.
+                prop_assert!((count as f64 - expected).abs() < 6.0 * expected.sqrt() + 10.0);
             }
         }
     }